@@ -0,0 +1,502 @@
+//! Parsing of the `#[builder(...)]` attributes on the derived type, its variants and its fields.
+//!
+//! Attributes other than `builder` are ignored. Inside `builder(...)`, every option is checked
+//! for an unknown or repeated key and for a value of the wrong kind, and all such mistakes are
+//! reported together, each pointing at the offending tokens.
+
+use quote::{format_ident, quote};
+
+/// Options collected from the `#[builder(...)]` attributes of the struct.
+#[derive(Default)]
+pub(crate) struct StructAttrs {
+    /// Name of the builder type, from `name = "..."`, defaulting to `<Struct>Builder`.
+    pub(crate) name: std::option::Option<syn::Ident>,
+    /// Visibility of the builder type and its methods, from `vis = "..."`, defaulting to `pub`.
+    pub(crate) vis: std::option::Option<syn::Visibility>,
+    /// Traits derived for the builder type, from `derive(...)`.
+    pub(crate) derives: Vec<syn::Path>,
+    /// Setter options applied to every field unless the field overrides them.
+    pub(crate) setter: SetterAttrs,
+    pub(crate) build_fn: BuildFnAttrs,
+    /// Track the required fields in the builder's type, from `typestate`.
+    pub(crate) typestate: bool,
+}
+
+impl StructAttrs {
+    pub(crate) fn builder_ident(&self, struct_ident: &syn::Ident) -> syn::Ident {
+        match &self.name {
+            std::option::Option::Some(name) => name.clone(),
+            std::option::Option::None => format_ident!("{}Builder", struct_ident),
+        }
+    }
+
+    pub(crate) fn vis(&self) -> syn::Visibility {
+        match &self.vis {
+            std::option::Option::Some(vis) => vis.clone(),
+            std::option::Option::None => syn::parse_quote!(pub),
+        }
+    }
+
+    pub(crate) fn derives_deserialize(&self) -> bool {
+        self.derives.iter().any(is_deserialize)
+    }
+
+    pub(crate) fn derive_attr(&self) -> std::option::Option<proc_macro2::TokenStream> {
+        if self.derives.is_empty() {
+            return std::option::Option::None;
+        }
+        let derives = &self.derives;
+        std::option::Option::Some(quote!(#[derive(#(#derives),*)]))
+    }
+}
+
+/// Options collected from the `#[builder(...)]` attributes of an enum variant.
+#[derive(Default)]
+pub(crate) struct VariantAttrs {
+    /// Name of the variant's builder type, from `name = "..."`, defaulting to
+    /// `<Enum><Variant>Builder`.
+    pub(crate) name: std::option::Option<syn::Ident>,
+}
+
+/// Options from `build_fn(...)`, controlling the generated `build` method.
+#[derive(Default)]
+pub(crate) struct BuildFnAttrs {
+    /// Function called with the populated builder before the struct is constructed.
+    pub(crate) validate: std::option::Option<syn::Path>,
+    /// Generate `async fn build(self)`, from `async`, kept for error reporting.
+    ///
+    /// The builder is then consumed by `build`, so its setters take and return it by value, and
+    /// `build` can await the `default_with_async` functions.
+    pub(crate) asyncness: std::option::Option<syn::Path>,
+}
+
+/// Options collected from the `#[builder(...)]` attributes of a field, on top of the struct ones.
+pub(crate) struct FieldAttrs {
+    /// Name of a tuple field's builder field and setter, from `name = "..."`.
+    pub(crate) name: std::option::Option<syn::Ident>,
+    /// Expression initializing a field left out of the builder, from `skip` or `skip = "..."`.
+    pub(crate) skip: std::option::Option<syn::Expr>,
+    /// Name of the method that appends a single element, from `each = "..."`.
+    pub(crate) each: std::option::Option<syn::Ident>,
+    /// Function called by `build` for a value when the field was not set, from
+    /// `default_with = "..."` or `default_with_async = "..."`. It returns either the field type
+    /// or a `Result` of it.
+    pub(crate) default_with: std::option::Option<syn::Path>,
+    /// Whether `default_with` is an `async fn` to await, which needs `build_fn(async)`.
+    pub(crate) default_async: bool,
+    /// Whether `build` fails when the field is unset, forced with `required` or `optional`
+    /// instead of being inferred from the field type.
+    pub(crate) required: std::option::Option<bool>,
+    pub(crate) setter: SetterAttrs,
+    /// Visibility of the setters, from `vis = "..."`, defaulting to the builder's.
+    pub(crate) vis: syn::Visibility,
+    /// The `///` comments of the field, copied onto its setters.
+    pub(crate) docs: Vec<syn::Attribute>,
+}
+
+/// Options from `setter(...)`, controlling the signature of the generated setters.
+#[derive(Clone, Default)]
+pub(crate) struct SetterAttrs {
+    /// Accept `impl Into<T>` instead of `T`.
+    pub(crate) into: bool,
+    /// Whether `Option<T>` fields get a setter taking `T`, `None` meaning the default (`true`).
+    pub(crate) strip_option: std::option::Option<bool>,
+    /// Prepended to the name of every setter generated for the field, from `prefix = "..."`:
+    /// `<prefix><field>`, `<prefix><field>_opt`, `<prefix>clear_<field>` and
+    /// `<prefix>extend_<field>`. The name given to `each` is used as is.
+    pub(crate) prefix: std::option::Option<String>,
+    /// Generate no setter, the field defaulting to `Default::default()` unless merged in.
+    pub(crate) skip: bool,
+}
+
+impl SetterAttrs {
+    pub(crate) fn method_name(&self, field_name: &syn::Ident) -> syn::Ident {
+        match &self.prefix {
+            std::option::Option::Some(prefix) => format_ident!("{}{}", prefix, field_name),
+            std::option::Option::None => field_name.clone(),
+        }
+    }
+
+    pub(crate) fn opt_method_name(&self, field_name: &syn::Ident) -> syn::Ident {
+        self.method_name(&format_ident!("{}_opt", field_name))
+    }
+
+    pub(crate) fn clear_method_name(&self, field_name: &syn::Ident) -> syn::Ident {
+        self.method_name(&format_ident!("clear_{}", field_name))
+    }
+
+    pub(crate) fn extend_method_name(&self, field_name: &syn::Ident) -> syn::Ident {
+        self.method_name(&format_ident!("extend_{}", field_name))
+    }
+}
+
+const STRUCT_KEYS: &[&str] = &["name", "vis", "derive", "setter", "build_fn", "typestate"];
+const VARIANT_KEYS: &[&str] = &["name"];
+const FIELD_KEYS: &[&str] = &[
+    "name",
+    "vis",
+    "each",
+    "skip",
+    "setter",
+    "default_with",
+    "default_with_async",
+    "required",
+    "optional",
+];
+const STRUCT_SETTER_KEYS: &[&str] = &["into", "strip_option", "prefix"];
+const FIELD_SETTER_KEYS: &[&str] = &["into", "strip_option", "prefix", "skip"];
+const BUILD_FN_KEYS: &[&str] = &["validate", "async"];
+
+pub(crate) fn parse_struct_attrs(attrs: &[syn::Attribute]) -> syn::Result<StructAttrs> {
+    let mut struct_attrs = StructAttrs::default();
+    parse_builder_attrs(attrs, STRUCT_KEYS, |key, meta| {
+        match key {
+            "name" => struct_attrs.name = std::option::Option::Some(lit_str(meta)?.parse()?),
+            "vis" => struct_attrs.vis = std::option::Option::Some(lit_str(meta)?.parse()?),
+            "derive" => {
+                for nested in &list(meta)?.nested {
+                    match nested {
+                        syn::NestedMeta::Meta(syn::Meta::Path(path))
+                            if is_deserialize(path) && !cfg!(feature = "serde") =>
+                        {
+                            return std::result::Result::Err(syn::Error::new_spanned(
+                                path,
+                                "`derive(Deserialize)` requires the `serde` feature of derive_builder",
+                            ));
+                        }
+                        syn::NestedMeta::Meta(syn::Meta::Path(path)) => {
+                            struct_attrs.derives.push(path.clone());
+                        }
+                        _ => {
+                            return std::result::Result::Err(syn::Error::new_spanned(
+                                nested,
+                                "expected a trait name",
+                            ))
+                        }
+                    }
+                }
+            }
+            "setter" => {
+                parse_setter_attrs(list(meta)?, STRUCT_SETTER_KEYS, &mut struct_attrs.setter)?
+            }
+            "build_fn" => parse_build_fn_attrs(list(meta)?, &mut struct_attrs.build_fn)?,
+            "typestate" => struct_attrs.typestate = flag(meta)?,
+            _ => unreachable!(),
+        }
+        std::result::Result::Ok(())
+    })?;
+    std::result::Result::Ok(struct_attrs)
+}
+
+pub(crate) fn parse_variant_attrs(attrs: &[syn::Attribute]) -> syn::Result<VariantAttrs> {
+    let mut variant_attrs = VariantAttrs::default();
+    parse_builder_attrs(attrs, VARIANT_KEYS, |key, meta| {
+        match key {
+            "name" => variant_attrs.name = std::option::Option::Some(lit_str(meta)?.parse()?),
+            _ => unreachable!(),
+        }
+        std::result::Result::Ok(())
+    })?;
+    std::result::Result::Ok(variant_attrs)
+}
+
+pub(crate) fn parse_field_attrs(
+    attrs: &[syn::Attribute],
+    struct_attrs: &StructAttrs,
+) -> syn::Result<FieldAttrs> {
+    let mut field_attrs = FieldAttrs {
+        name: std::option::Option::None,
+        skip: std::option::Option::None,
+        each: std::option::Option::None,
+        default_with: std::option::Option::None,
+        default_async: false,
+        required: std::option::Option::None,
+        setter: struct_attrs.setter.clone(),
+        vis: struct_attrs.vis(),
+        docs: attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("doc"))
+            .cloned()
+            .collect(),
+    };
+    let mut skip_path = std::option::Option::None;
+    let mut presence_path = std::option::Option::None;
+    let mut other_paths = Vec::new();
+    parse_builder_attrs(attrs, FIELD_KEYS, |key, meta| {
+        match key {
+            "name" => field_attrs.name = std::option::Option::Some(lit_str(meta)?.parse()?),
+            "vis" => field_attrs.vis = lit_str(meta)?.parse()?,
+            "each" => field_attrs.each = std::option::Option::Some(lit_str(meta)?.parse()?),
+            "default_with" | "default_with_async" => {
+                if field_attrs.default_with.is_some() {
+                    return std::result::Result::Err(syn::Error::new_spanned(
+                        meta.path(),
+                        "`default_with` cannot be combined with `default_with_async`",
+                    ));
+                }
+                if key == "default_with_async" && struct_attrs.build_fn.asyncness.is_none() {
+                    return std::result::Result::Err(syn::Error::new_spanned(
+                        meta.path(),
+                        "`default_with_async` needs an async `build`, from `build_fn(async)`",
+                    ));
+                }
+                field_attrs.default_with = std::option::Option::Some(lit_str(meta)?.parse()?);
+                field_attrs.default_async = key == "default_with_async";
+            }
+            "skip" => {
+                field_attrs.skip = match meta {
+                    syn::Meta::Path(_) => std::option::Option::Some(syn::parse_quote!(
+                        std::default::Default::default()
+                    )),
+                    _ => std::option::Option::Some(lit_str(meta)?.parse()?),
+                };
+            }
+            "setter" => {
+                parse_setter_attrs(list(meta)?, FIELD_SETTER_KEYS, &mut field_attrs.setter)?
+            }
+            "required" | "optional" => {
+                if let std::option::Option::Some(presence_path) = &presence_path {
+                    return std::result::Result::Err(syn::Error::new_spanned(
+                        meta.path(),
+                        format!(
+                            "`{}` cannot be combined with `{}`",
+                            key,
+                            quote!(#presence_path)
+                        ),
+                    ));
+                }
+                if flag(meta)? {
+                    field_attrs.required = std::option::Option::Some(key == "required");
+                }
+                presence_path = std::option::Option::Some(meta.path().clone());
+            }
+            _ => unreachable!(),
+        }
+        if key == "skip" {
+            skip_path = std::option::Option::Some(meta.path().clone());
+        } else {
+            other_paths.push(meta.path().clone());
+        }
+        std::result::Result::Ok(())
+    })?;
+    if skip_path.is_some() {
+        if let std::option::Option::Some(other_path) = other_paths.first() {
+            return std::result::Result::Err(syn::Error::new_spanned(
+                other_path,
+                "skipped fields have no setter, `skip` cannot be combined with other options",
+            ));
+        }
+    }
+    if let (std::option::Option::Some(each), true) = (&field_attrs.each, field_attrs.setter.skip) {
+        return std::result::Result::Err(syn::Error::new_spanned(
+            each,
+            "`each` cannot be combined with `setter(skip)`",
+        ));
+    }
+    if let (std::option::Option::Some(true), std::option::Option::Some(presence_path)) =
+        (field_attrs.required, &presence_path)
+    {
+        let conflict = if field_attrs.each.is_some() {
+            std::option::Option::Some("`each`, whose collection starts out empty")
+        } else if field_attrs.default_with.is_some() {
+            std::option::Option::Some("`default_with`")
+        } else if field_attrs.setter.skip {
+            std::option::Option::Some("`setter(skip)`")
+        } else {
+            std::option::Option::None
+        };
+        if let std::option::Option::Some(conflict) = conflict {
+            return std::result::Result::Err(syn::Error::new_spanned(
+                presence_path,
+                format!("`required` cannot be combined with {}", conflict),
+            ));
+        }
+    }
+    std::result::Result::Ok(field_attrs)
+}
+
+pub(crate) fn is_deserialize(path: &syn::Path) -> bool {
+    path.segments
+        .last()
+        .is_some_and(|segment| segment.ident == "Deserialize")
+}
+
+fn parse_build_fn_attrs(list: &syn::MetaList, build_fn: &mut BuildFnAttrs) -> syn::Result<()> {
+    parse_options(
+        list.nested.iter(),
+        "build_fn",
+        BUILD_FN_KEYS,
+        |key, meta| {
+            match key {
+                "validate" => {
+                    build_fn.validate = std::option::Option::Some(lit_str(meta)?.parse()?)
+                }
+                "async" if flag(meta)? => {
+                    build_fn.asyncness = std::option::Option::Some(meta.path().clone())
+                }
+                "async" => build_fn.asyncness = std::option::Option::None,
+                _ => unreachable!(),
+            }
+            std::result::Result::Ok(())
+        },
+    )
+}
+
+fn parse_setter_attrs(
+    list: &syn::MetaList,
+    keys: &[&str],
+    setter: &mut SetterAttrs,
+) -> syn::Result<()> {
+    parse_options(list.nested.iter(), "setter", keys, |key, meta| {
+        match key {
+            "into" => setter.into = flag(meta)?,
+            "strip_option" => setter.strip_option = std::option::Option::Some(flag(meta)?),
+            "prefix" => setter.prefix = std::option::Option::Some(lit_str(meta)?.value()),
+            "skip" => setter.skip = flag(meta)?,
+            _ => unreachable!(),
+        }
+        std::result::Result::Ok(())
+    })
+}
+
+/// Hand every option of every `#[builder(...)]` attribute to `f`, see [`parse_options`].
+fn parse_builder_attrs(
+    attrs: &[syn::Attribute],
+    keys: &[&str],
+    f: impl FnMut(&str, &syn::Meta) -> syn::Result<()>,
+) -> syn::Result<()> {
+    let mut lists = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("builder")) {
+        match attr.parse_meta()? {
+            syn::Meta::List(list) => lists.push(list),
+            meta => {
+                return std::result::Result::Err(syn::Error::new_spanned(
+                    meta,
+                    "expected `builder(...)`",
+                ))
+            }
+        }
+    }
+    parse_options(
+        lists.iter().flat_map(|list| list.nested.iter()),
+        "builder",
+        keys,
+        f,
+    )
+}
+
+/// Hand each option to `f` along with its key, which is guaranteed to be one of `keys`.
+///
+/// Literals, unknown keys and keys given more than once are rejected, as is any error returned
+/// by `f`. Errors don't stop the parsing, they are all combined into the returned one.
+fn parse_options<'a>(
+    nested: impl Iterator<Item = &'a syn::NestedMeta>,
+    attr: &str,
+    keys: &[&str],
+    mut f: impl FnMut(&str, &syn::Meta) -> syn::Result<()>,
+) -> syn::Result<()> {
+    let mut seen = std::collections::HashSet::new();
+    let mut errors: std::option::Option<syn::Error> = std::option::Option::None;
+    for nested in nested {
+        let result = match nested {
+            syn::NestedMeta::Lit(lit) => std::result::Result::Err(syn::Error::new_spanned(
+                lit,
+                format!("expected an option of `{}(...)`, found a literal", attr),
+            )),
+            syn::NestedMeta::Meta(meta) => {
+                let path = meta.path();
+                let key = quote!(#path).to_string().replace(' ', "");
+                if !keys.contains(&key.as_str()) {
+                    std::result::Result::Err(syn::Error::new_spanned(
+                        path,
+                        format!(
+                            "unknown option `{}` in `{}(...)`, expected one of {}",
+                            key,
+                            attr,
+                            keys.iter()
+                                .map(|key| format!("`{}`", key))
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                        ),
+                    ))
+                } else if !seen.insert(key.clone()) {
+                    std::result::Result::Err(syn::Error::new_spanned(
+                        path,
+                        format!("duplicate option `{}` in `{}(...)`", key, attr),
+                    ))
+                } else {
+                    f(&key, meta)
+                }
+            }
+        };
+        if let std::result::Result::Err(err) = result {
+            match &mut errors {
+                std::option::Option::Some(errors) => errors.combine(err),
+                std::option::Option::None => errors = std::option::Option::Some(err),
+            }
+        }
+    }
+    match errors {
+        std::option::Option::Some(errors) => std::result::Result::Err(errors),
+        std::option::Option::None => std::result::Result::Ok(()),
+    }
+}
+
+/// Expect a `key = "..."` option.
+fn lit_str(meta: &syn::Meta) -> syn::Result<&syn::LitStr> {
+    match meta {
+        syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(lit_str),
+            ..
+        }) => std::result::Result::Ok(lit_str),
+        syn::Meta::NameValue(syn::MetaNameValue { lit, .. }) => {
+            std::result::Result::Err(syn::Error::new_spanned(lit, "expected a string literal"))
+        }
+        _ => {
+            let path = meta.path();
+            std::result::Result::Err(syn::Error::new_spanned(
+                meta,
+                format!("expected `{} = \"...\"`", quote!(#path)),
+            ))
+        }
+    }
+}
+
+/// Expect a `key` or `key = <bool>` option.
+fn flag(meta: &syn::Meta) -> syn::Result<bool> {
+    match meta {
+        syn::Meta::Path(_) => std::result::Result::Ok(true),
+        syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Bool(lit_bool),
+            ..
+        }) => std::result::Result::Ok(lit_bool.value),
+        syn::Meta::NameValue(syn::MetaNameValue { lit, .. }) => {
+            std::result::Result::Err(syn::Error::new_spanned(lit, "expected `true` or `false`"))
+        }
+        syn::Meta::List(list) => {
+            let path = &list.path;
+            std::result::Result::Err(syn::Error::new_spanned(
+                meta,
+                format!(
+                    "expected `{}` or `{} = true|false`",
+                    quote!(#path),
+                    quote!(#path)
+                ),
+            ))
+        }
+    }
+}
+
+/// Expect a `key(...)` option.
+fn list(meta: &syn::Meta) -> syn::Result<&syn::MetaList> {
+    match meta {
+        syn::Meta::List(list) => std::result::Result::Ok(list),
+        _ => {
+            let path = meta.path();
+            std::result::Result::Err(syn::Error::new_spanned(
+                meta,
+                format!("expected `{}(...)`", quote!(#path)),
+            ))
+        }
+    }
+}
@@ -1,221 +1,1045 @@
 // extern crate proc_macro;
 
+mod attrs;
+mod typestate;
+
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
 use syn::{parse_macro_input, DeriveInput};
 
+use attrs::{
+    parse_field_attrs, parse_struct_attrs, parse_variant_attrs, FieldAttrs, SetterAttrs,
+    StructAttrs,
+};
+
 #[proc_macro_derive(Builder, attributes(builder))]
 pub fn derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
-    let struct_ident_original = input.ident;
-    let original_struct_impl = impl_struct(&struct_ident_original, &input.data).unwrap();
-    let builder_struct = builder_struct(&struct_ident_original, &input.data).unwrap();
-    let builder_impl = builder_impl(&struct_ident_original, &input.data).unwrap();
-
-    quote!(
-        #original_struct_impl
-        #builder_struct
-        #builder_impl
-    )
+    match expand(&input) {
+        std::result::Result::Ok(tt) => tt,
+        std::result::Result::Err(err) => err.to_compile_error(),
+    }
     .into()
 }
 
-fn impl_struct(struct_ident: &syn::Ident, data: &syn::Data) -> std::option::Option<proc_macro2::TokenStream> {
-    if let syn::Data::Struct(syn::DataStruct {
-        fields: syn::Fields::Named(syn::FieldsNamed { named, .. }),
-        ..
-    }) = data
-    {
-        let builder_struct_inits = named.iter().map(initialize_field);
-        let builder_struct_ident = format_ident!("{}Builder", struct_ident);
-        std::option::Option::Some(quote! (
-            impl #struct_ident {
-                pub fn builder() -> #builder_struct_ident {
-                    #builder_struct_ident {
-                        #(#builder_struct_inits),*
-                    }
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let struct_attrs = parse_struct_attrs(&input.attrs)?;
+    let builders = targets(input, &struct_attrs)?
+        .iter()
+        .map(|target| {
+            if struct_attrs.typestate {
+                typestate::typestate_builder(&input.ident, target, &struct_attrs)
+            } else {
+                let original_struct_impl = impl_struct(&input.ident, target, &struct_attrs);
+                let builder_struct = builder_struct(&input.ident, target, &struct_attrs);
+                let builder_impl = builder_impl(&input.ident, target, &struct_attrs);
+                let builder_from = builder_from(&input.ident, target, &struct_attrs);
+                std::result::Result::Ok(quote!(
+                    #original_struct_impl
+                    #builder_struct
+                    #builder_impl
+                    #builder_from
+                ))
+            }
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    std::result::Result::Ok(quote!(#(#builders)*))
+}
+
+/// A struct, or a single enum variant, for which a builder is generated.
+struct Target {
+    /// Name of the generated builder type.
+    builder_ident: syn::Ident,
+    /// Name of the method on the derived type returning a fresh builder.
+    builder_fn: syn::Ident,
+    /// Path used to construct the value, e.g. `Command` or `Shape::Circle`.
+    path: proc_macro2::TokenStream,
+    /// Whether the target is an enum variant rather than the whole derived type.
+    is_variant: bool,
+    /// Fields set through the builder.
+    fields: Vec<TargetField>,
+    /// Fields left out of the builder with `skip`.
+    skipped: Vec<SkippedField>,
+}
+
+/// A field set through the builder.
+struct TargetField {
+    /// Member of the target initialized by the field.
+    member: syn::Member,
+    /// The field itself.
+    ///
+    /// Tuple fields are given an ident (`_0`, `_1`, ... or the one from `name = "..."`),
+    /// which names both the builder field and its setter.
+    field: syn::Field,
+    attrs: FieldAttrs,
+}
+
+/// A field left out of the builder, initialized from an expression in `build`.
+struct SkippedField {
+    member: syn::Member,
+    init: syn::Expr,
+}
+
+fn targets(input: &DeriveInput, struct_attrs: &StructAttrs) -> syn::Result<Vec<Target>> {
+    let ident = &input.ident;
+    match &input.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Unit,
+            ..
+        }) => std::result::Result::Err(syn::Error::new_spanned(
+            ident,
+            "Builder cannot be derived for unit structs",
+        )),
+        syn::Data::Struct(syn::DataStruct { fields, .. }) => {
+            let (fields, skipped) = target_fields(fields, struct_attrs)?;
+            std::result::Result::Ok(vec![Target {
+                builder_ident: struct_attrs.builder_ident(ident),
+                builder_fn: format_ident!("builder"),
+                path: quote!(#ident),
+                is_variant: false,
+                fields,
+                skipped,
+            }])
+        }
+        syn::Data::Enum(syn::DataEnum { variants, .. }) => {
+            if let std::option::Option::Some(name) = &struct_attrs.name {
+                return std::result::Result::Err(syn::Error::new_spanned(
+                    name,
+                    "`name` is not supported on enums, every variant gets its own builder",
+                ));
+            }
+            variants
+                .iter()
+                .map(|variant| {
+                    let variant_ident = &variant.ident;
+                    let variant_attrs = parse_variant_attrs(&variant.attrs)?;
+                    let (fields, skipped) = target_fields(&variant.fields, struct_attrs)?;
+                    std::result::Result::Ok(Target {
+                        builder_ident: variant_attrs
+                            .name
+                            .unwrap_or_else(|| format_ident!("{}{}Builder", ident, variant_ident)),
+                        builder_fn: format_ident!("{}_builder", to_snake_case(variant_ident)),
+                        path: quote!(#ident::#variant_ident),
+                        is_variant: true,
+                        fields,
+                        skipped,
+                    })
+                })
+                .collect()
+        }
+        syn::Data::Union(syn::DataUnion { union_token, .. }) => std::result::Result::Err(
+            syn::Error::new_spanned(union_token, "Builder cannot be derived for unions"),
+        ),
+    }
+}
+
+/// Split the fields into the ones set through the builder and the skipped ones.
+fn target_fields(
+    fields: &syn::Fields,
+    struct_attrs: &StructAttrs,
+) -> syn::Result<(Vec<TargetField>, Vec<SkippedField>)> {
+    let mut target_fields = Vec::new();
+    let mut skipped = Vec::new();
+    let mut errors: std::option::Option<syn::Error> = std::option::Option::None;
+    for (idx, field) in fields.iter().enumerate() {
+        // Keep going past a field with broken attributes so that every field gets reported.
+        let mut field_attrs = match parse_field_attrs(&field.attrs, struct_attrs) {
+            std::result::Result::Ok(field_attrs) => field_attrs,
+            std::result::Result::Err(err) => {
+                match &mut errors {
+                    std::option::Option::Some(errors) => errors.combine(err),
+                    std::option::Option::None => errors = std::option::Option::Some(err),
                 }
+                continue;
             }
-        ))
+        };
+        let member = match &field.ident {
+            std::option::Option::Some(ident) => syn::Member::Named(ident.clone()),
+            std::option::Option::None => syn::Member::Unnamed(syn::Index::from(idx)),
+        };
+        if let std::option::Option::Some(init) = field_attrs.skip {
+            skipped.push(SkippedField { member, init });
+            continue;
+        }
+        if let std::option::Option::Some(default_with) = &field_attrs.default_with {
+            if is_collection(field, &field_attrs) {
+                return std::result::Result::Err(syn::Error::new_spanned(
+                    default_with,
+                    "`default_with` is not supported on collections, which always start out empty",
+                ));
+            }
+        }
+        let mut field = field.clone();
+        match (&field.ident, field_attrs.name.take()) {
+            (std::option::Option::Some(_), std::option::Option::Some(name)) => {
+                return std::result::Result::Err(syn::Error::new_spanned(
+                    name,
+                    "`name` is only supported on tuple fields",
+                ));
+            }
+            (std::option::Option::Some(_), std::option::Option::None) => {}
+            (std::option::Option::None, name) => {
+                field.ident =
+                    std::option::Option::Some(name.unwrap_or_else(|| format_ident!("_{}", idx)));
+            }
+        }
+        target_fields.push(TargetField {
+            member,
+            field,
+            attrs: field_attrs,
+        });
+    }
+    if let std::option::Option::Some(errors) = errors {
+        return std::result::Result::Err(errors);
+    }
+    check_method_names(&target_fields, struct_attrs)?;
+    std::result::Result::Ok((target_fields, skipped))
+}
+
+/// Methods of every builder, which no method generated for a field can be named after.
+const BUILDER_METHODS: &[&str] = &["build"];
+
+/// Methods of the builders but the typestate ones, on top of the `BUILDER_METHODS`.
+const NON_TYPESTATE_BUILDER_METHODS: &[&str] = &["merge", "missing_fields", "is_complete"];
+
+/// Report a method generated for a field which has the name of a builder method, or of a method
+/// generated for an earlier field, instead of letting the two definitions collide.
+fn check_method_names(
+    target_fields: &[TargetField],
+    struct_attrs: &StructAttrs,
+) -> syn::Result<()> {
+    let mut owners = std::collections::HashMap::new();
+    for builder_method in BUILDER_METHODS.iter().chain(if struct_attrs.typestate {
+        &[]
     } else {
-        std::option::Option::None
+        NON_TYPESTATE_BUILDER_METHODS
+    }) {
+        owners.insert(builder_method.to_string(), std::option::Option::None);
     }
+    for target_field in target_fields {
+        let field_name = target_field.field.ident.as_ref().unwrap();
+        for method_name in
+            field_method_names(&target_field.field, &target_field.attrs, struct_attrs)
+        {
+            let owner = match owners.get(&method_name.to_string()) {
+                std::option::Option::Some(owner) => owner,
+                std::option::Option::None => {
+                    owners.insert(
+                        method_name.to_string(),
+                        std::option::Option::Some(field_name),
+                    );
+                    continue;
+                }
+            };
+            let clash = match owner {
+                std::option::Option::Some(owner) => format!("a method generated for `{}`", owner),
+                std::option::Option::None => "the builder's own method".to_owned(),
+            };
+            return std::result::Result::Err(syn::Error::new_spanned(
+                &target_field.field,
+                format!(
+                    "method `{}` generated for this field clashes with {}, \
+                     rename it with `setter(prefix = \"...\")` or `each = \"...\"`",
+                    method_name, clash,
+                ),
+            ));
+        }
+    }
+    std::result::Result::Ok(())
 }
 
-fn builder_struct(struct_ident: &syn::Ident, data: &syn::Data) -> std::option::Option<proc_macro2::TokenStream> {
-    if let syn::Data::Struct(syn::DataStruct {
-        fields: syn::Fields::Named(syn::FieldsNamed { named, .. }),
-        ..
-    }) = data
-    {
-        let builder_struct_fields = named.iter().map(optionize_field);
-        let builder_struct_ident = format_ident!("{}Builder", struct_ident);
-        std::option::Option::Some(quote!(
-            pub struct #builder_struct_ident {
-                #(#builder_struct_fields),*
+/// Names of the builder methods generated for a field, following `functionize_field` and
+/// `getter`.
+fn field_method_names(
+    field: &syn::Field,
+    field_attrs: &FieldAttrs,
+    struct_attrs: &StructAttrs,
+) -> Vec<syn::Ident> {
+    let field_name = field.ident.as_ref().unwrap();
+    let mut method_names = Vec::new();
+    if !struct_attrs.typestate {
+        method_names.push(format_ident!("{}_ref", field_name));
+    }
+    if field_attrs.setter.skip {
+        return method_names;
+    }
+    let setter_name = field_attrs.setter.method_name(field_name);
+    match &field_attrs.each {
+        std::option::Option::Some(each) => {
+            if each != field_name {
+                method_names.push(setter_name.clone());
             }
-        ))
-    } else {
-        std::option::Option::None
+            method_names.push(each.clone());
+            method_names.push(field_attrs.setter.extend_method_name(field_name));
+        }
+        std::option::Option::None => method_names.push(setter_name.clone()),
     }
+    if option_inner_type(field, field_attrs).is_some() {
+        if field_attrs.setter.strip_option.unwrap_or(true) {
+            method_names.push(field_attrs.setter.opt_method_name(field_name));
+        }
+        method_names.push(field_attrs.setter.clear_method_name(field_name));
+    }
+    method_names
 }
 
-fn builder_impl(struct_ident: &syn::Ident, data: &syn::Data) -> std::option::Option<proc_macro2::TokenStream> {
-    if let syn::Data::Struct(syn::DataStruct {
-        fields: syn::Fields::Named(syn::FieldsNamed { named, .. }),
-        ..
-    }) = data
-    {
-        let builder_impl_functions = named.iter().map(functionize_field);
-        let builder_fields = named.iter().map(assign_field);
-        let builder_struct_ident = format_ident!("{}Builder", struct_ident);
-        std::option::Option::Some(quote!(
-            impl #builder_struct_ident {
-                pub fn build(&mut self) -> std::result::Result<#struct_ident, std::boxed::Box<dyn std::error::Error>> {
-                    std::result::Result::Ok(
-                        #struct_ident {
-                            #(#builder_fields),*
-                        }
-                    )
+/// Convert a variant name to snake case, keeping runs of capitals like `HTTP` in `HTTPHeader`
+/// together as one word.
+fn to_snake_case(ident: &syn::Ident) -> String {
+    let chars = ident.to_string().chars().collect::<Vec<_>>();
+    let mut snake_case = String::new();
+    for (idx, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && idx != 0 {
+            let prev = chars[idx - 1];
+            let next_is_lowercase = chars.get(idx + 1).is_some_and(|next| next.is_lowercase());
+            if !prev.is_uppercase() || next_is_lowercase {
+                snake_case.push('_');
+            }
+        }
+        snake_case.extend(c.to_lowercase());
+    }
+    snake_case
+}
+
+fn impl_struct(
+    struct_ident: &syn::Ident,
+    target: &Target,
+    struct_attrs: &StructAttrs,
+) -> proc_macro2::TokenStream {
+    let builder_struct_inits = target
+        .fields
+        .iter()
+        .map(|target_field| initialize_field(&target_field.field, &target_field.attrs));
+    let builder_struct_ident = &target.builder_ident;
+    let builder_fn = &target.builder_fn;
+    let vis = struct_attrs.vis();
+    let doc = format!("Create a [`{}`] with no field set.", builder_struct_ident);
+    quote! (
+        impl #struct_ident {
+            #[doc = #doc]
+            #vis fn #builder_fn() -> #builder_struct_ident {
+                #builder_struct_ident {
+                    #(#builder_struct_inits),*
                 }
-                #(#builder_impl_functions)*
             }
-        ))
+        }
+    )
+}
+
+fn builder_struct(
+    struct_ident: &syn::Ident,
+    target: &Target,
+    struct_attrs: &StructAttrs,
+) -> proc_macro2::TokenStream {
+    let builder_struct_fields = target
+        .fields
+        .iter()
+        .map(|target_field| optionize_field(&target_field.field, &target_field.attrs));
+    let builder_struct_ident = &target.builder_ident;
+    let vis = struct_attrs.vis();
+    let derives = struct_attrs.derive_attr();
+    let doc = builder_doc(struct_ident, target);
+    // Fields missing from the input are left as they are in a fresh builder, so that
+    // collections start out empty rather than unset.
+    let serde_attr = if struct_attrs.derives_deserialize() {
+        let default = format!("{}::{}", struct_ident, target.builder_fn);
+        std::option::Option::Some(quote!(#[serde(default = #default)]))
+    } else {
+        std::option::Option::None
+    };
+    quote!(
+        #doc
+        #derives
+        #serde_attr
+        #vis struct #builder_struct_ident {
+            #(#builder_struct_fields),*
+        }
+    )
+}
+
+fn builder_impl(
+    struct_ident: &syn::Ident,
+    target: &Target,
+    struct_attrs: &StructAttrs,
+) -> proc_macro2::TokenStream {
+    let asyncness = struct_attrs
+        .build_fn
+        .asyncness
+        .as_ref()
+        .map(|_| quote!(async));
+    // An async `build` has to own the builder, so the setters hand it over by value.
+    let receiver = if asyncness.is_some() {
+        SetterReceiver::Owned
+    } else {
+        SetterReceiver::Borrowed
+    };
+    let builder_impl_functions = target
+        .fields
+        .iter()
+        .map(|target_field| functionize_field(&target_field.field, &target_field.attrs, receiver));
+    let builder_fields = target.fields.iter().map(|target_field| {
+        assign_field(
+            &target_field.member,
+            &target_field.field,
+            &target_field.attrs,
+        )
+    });
+    let default_with = if target
+        .fields
+        .iter()
+        .any(|target_field| target_field.attrs.default_with.is_some())
+    {
+        std::option::Option::Some(default_with_trait())
     } else {
         std::option::Option::None
+    };
+    let skipped_fields = target.skipped.iter().map(skip_field);
+    let merge_fields = target
+        .fields
+        .iter()
+        .map(|target_field| merge_field(&target_field.field, &target_field.attrs));
+    let builder_struct_ident = &target.builder_ident;
+    let target_path = &target.path;
+    let vis = struct_attrs.vis();
+    let validate = struct_attrs
+        .build_fn
+        .validate
+        .as_ref()
+        .map(|validate| quote!(#validate(&self)?;));
+    let build_receiver = match receiver {
+        SetterReceiver::Borrowed => quote!(&mut self),
+        SetterReceiver::Owned => quote!(self),
+    };
+    let build_doc = build_doc(target, struct_attrs);
+    let getters = target
+        .fields
+        .iter()
+        .map(|target_field| getter(&target_field.field, &target_field.attrs));
+    let required_names = target
+        .fields
+        .iter()
+        .filter(|target_field| is_required(&target_field.field, &target_field.attrs))
+        .map(|target_field| &target_field.field.ident)
+        .collect::<Vec<_>>();
+    let required_strs = required_names
+        .iter()
+        .map(|field_name| field_name.as_ref().unwrap().to_string());
+    let merge_receiver = receiver.receiver();
+    let merge_return_type = receiver.return_type();
+    quote!(
+        impl #builder_struct_ident {
+            #build_doc
+            #vis #asyncness fn build(#build_receiver) -> std::result::Result<#struct_ident, std::boxed::Box<dyn std::error::Error>> {
+                #default_with
+                #validate
+                std::result::Result::Ok(
+                    #target_path {
+                        #(#builder_fields,)*
+                        #(#skipped_fields),*
+                    }
+                )
+            }
+            /// Overlay the fields set in `other` on top of this builder.
+            ///
+            /// Collection fields are extended with the items of `other`, any other field set in
+            /// `other` replaces the value in this builder.
+            #vis fn merge(#merge_receiver, other: #builder_struct_ident) -> #merge_return_type {
+                #(#merge_fields)*
+                self
+            }
+            /// Names of the required fields which are not set yet.
+            #vis fn missing_fields(&self) -> std::vec::Vec<&'static str> {
+                #[allow(unused_mut)]
+                let mut missing_fields = std::vec::Vec::new();
+                #(
+                    if self.#required_names.is_none() {
+                        missing_fields.push(#required_strs);
+                    }
+                )*
+                missing_fields
+            }
+            /// Whether every required field is set, so that `build` can't fail for a missing field.
+            ///
+            /// It can still fail in validation, or in a `default_with` function returning an error.
+            #vis fn is_complete(&self) -> bool {
+                self.missing_fields().is_empty()
+            }
+            #(#getters)*
+            #(#builder_impl_functions)*
+        }
+    )
+}
+
+/// Generate `<field>_ref`, giving access to the value of the field set so far.
+fn getter(field: &syn::Field, field_attrs: &FieldAttrs) -> proc_macro2::TokenStream {
+    let field_name = field.ident.as_ref().unwrap();
+    let field_type = option_inner_type(field, field_attrs).unwrap_or(&field.ty);
+    let vis = &field_attrs.vis;
+    let method_name = format_ident!("{}_ref", field_name);
+    let doc = format!("The value of `{}`, if it was set.", field_name);
+    quote!(
+        #[doc = #doc]
+        #vis fn #method_name(&self) -> std::option::Option<&#field_type> {
+            self.#field_name.as_ref()
+        }
+    )
+}
+
+/// Generate the doc comment of the builder type, listing the fields `build` requires.
+fn builder_doc(struct_ident: &syn::Ident, target: &Target) -> proc_macro2::TokenStream {
+    let (required, optional): (Vec<_>, Vec<_>) = target
+        .fields
+        .iter()
+        .filter(|target_field| !target_field.attrs.setter.skip)
+        .partition(|target_field| is_required(&target_field.field, &target_field.attrs));
+    let mut lines = vec![
+        format!(
+            "Builder for [`{}`], created by [`{}::{}`].",
+            target.path.to_string().replace(' ', ""),
+            struct_ident,
+            target.builder_fn,
+        ),
+        String::new(),
+    ];
+    if required.is_empty() {
+        lines.push("No field needs to be set before building.".to_owned());
+    } else {
+        lines.push(format!("Required fields: {}.", field_list(&required)));
     }
+    if !optional.is_empty() {
+        lines.push(String::new());
+        lines.push(format!("Optional fields: {}.", field_list(&optional)));
+    }
+    quote!(#(#[doc = #lines])*)
+}
+
+/// Generate the doc comment of `build`, telling when it fails.
+fn build_doc(target: &Target, struct_attrs: &StructAttrs) -> proc_macro2::TokenStream {
+    let required = target
+        .fields
+        .iter()
+        .filter(|target_field| is_required(&target_field.field, &target_field.attrs))
+        .collect::<Vec<_>>();
+    let has_default_with = target
+        .fields
+        .iter()
+        .any(|target_field| target_field.attrs.default_with.is_some());
+    let mut lines = vec![format!(
+        "Build the [`{}`] from the fields set so far.",
+        target.path.to_string().replace(' ', "")
+    )];
+    let mut failures = Vec::new();
+    if !required.is_empty() {
+        failures.push(format!(
+            "one of the required fields {} was not set",
+            field_list(&required)
+        ));
+    }
+    if has_default_with {
+        failures.push("a `default_with` function fails".to_owned());
+    }
+    if let std::option::Option::Some(validate) = &struct_attrs.build_fn.validate {
+        failures.push(format!(
+            "`{}` rejects the builder",
+            quote!(#validate).to_string().replace(' ', "")
+        ));
+    }
+    if !failures.is_empty() {
+        lines.push(String::new());
+        lines.push(format!("Fails if {}.", failures.join(", or if ")));
+    }
+    quote!(#(#[doc = #lines])*)
 }
 
-fn initialize_field(field: &syn::Field) -> proc_macro2::TokenStream {
-    let ref field_name = field.ident;
-    let ref field_type = field.ty;
-    if extract_inner_type(field_type, "Vec").is_some() {
-        quote!(#field_name: std::option::Option::Some(vec!()))
+/// Format the names of `fields` as a comma separated list of code spans.
+fn field_list(fields: &[&TargetField]) -> String {
+    fields
+        .iter()
+        .map(|target_field| format!("`{}`", target_field.field.ident.as_ref().unwrap()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Generate `From<&Struct>` for the builder and `Struct::to_builder`, creating a builder with
+/// every field set from an existing value.
+fn builder_from(
+    struct_ident: &syn::Ident,
+    target: &Target,
+    struct_attrs: &StructAttrs,
+) -> std::option::Option<proc_macro2::TokenStream> {
+    if target.is_variant {
+        return std::option::Option::None;
+    }
+    let builder_struct_ident = &target.builder_ident;
+    let vis = struct_attrs.vis();
+    let from_fields = target.fields.iter().map(|target_field| {
+        let member = &target_field.member;
+        let field_name = &target_field.field.ident;
+        if option_inner_type(&target_field.field, &target_field.attrs).is_some() {
+            quote!(#field_name: existing.#member.clone())
+        } else {
+            quote!(#field_name: std::option::Option::Some(existing.#member.clone()))
+        }
+    });
+    std::option::Option::Some(quote!(
+        impl std::convert::From<&#struct_ident> for #builder_struct_ident {
+            fn from(existing: &#struct_ident) -> Self {
+                #builder_struct_ident {
+                    #(#from_fields),*
+                }
+            }
+        }
+
+        impl #struct_ident {
+            /// Create a builder with every field set from this value.
+            #vis fn to_builder(&self) -> #builder_struct_ident {
+                std::convert::From::from(self)
+            }
+        }
+    ))
+}
+
+fn initialize_field(field: &syn::Field, field_attrs: &FieldAttrs) -> proc_macro2::TokenStream {
+    let field_name = &field.ident;
+    if is_collection(field, field_attrs) {
+        quote!(#field_name: std::option::Option::Some(std::default::Default::default()))
     } else {
         quote!(#field_name: std::option::Option::None)
     }
 }
 
-fn assign_field(field: &syn::Field) -> proc_macro2::TokenStream {
-    let ref field_name = field.ident;
-    let ref field_type = field.ty;
-    if extract_inner_type(field_type, "Option").is_some() {
-        quote!(#field_name: self.#field_name.clone())
+fn assign_field(
+    member: &syn::Member,
+    field: &syn::Field,
+    field_attrs: &FieldAttrs,
+) -> proc_macro2::TokenStream {
+    let field_name = &field.ident;
+    let field_type = &field.ty;
+    if let std::option::Option::Some(default_with) = &field_attrs.default_with {
+        let await_default = if field_attrs.default_async {
+            std::option::Option::Some(quote!(.await))
+        } else {
+            std::option::Option::None
+        };
+        let default = quote!(
+            <_ as __DefaultWith<#field_type>>::into_field(#default_with() #await_default)?
+        );
+        if option_inner_type(field, field_attrs).is_some() {
+            quote!(#member: match self.#field_name.clone() {
+                value @ std::option::Option::Some(_) => value,
+                std::option::Option::None => #default,
+            })
+        } else {
+            quote!(#member: match self.#field_name.clone() {
+                std::option::Option::Some(value) => value,
+                std::option::Option::None => #default,
+            })
+        }
+    } else if option_inner_type(field, field_attrs).is_some() {
+        quote!(#member: self.#field_name.clone())
+    } else if !is_required(field, field_attrs) {
+        quote!(#member: self.#field_name.clone().unwrap_or_default())
+    } else {
+        quote!(#member: self.#field_name.clone().ok_or("field was not set")?)
+    }
+}
+
+/// Generate the trait through which `build` accepts a `default_with` function returning either
+/// the field type or a `Result` of it.
+fn default_with_trait() -> proc_macro2::TokenStream {
+    quote!(
+        trait __DefaultWith<T> {
+            fn into_field(self) -> std::result::Result<T, std::boxed::Box<dyn std::error::Error>>;
+        }
+        impl<T> __DefaultWith<T> for T {
+            fn into_field(self) -> std::result::Result<T, std::boxed::Box<dyn std::error::Error>> {
+                std::result::Result::Ok(self)
+            }
+        }
+        impl<T, E> __DefaultWith<T> for std::result::Result<T, E>
+        where
+            E: std::convert::Into<std::boxed::Box<dyn std::error::Error>>,
+        {
+            fn into_field(self) -> std::result::Result<T, std::boxed::Box<dyn std::error::Error>> {
+                self.map_err(std::convert::Into::into)
+            }
+        }
+    )
+}
+
+fn skip_field(skipped_field: &SkippedField) -> proc_macro2::TokenStream {
+    let member = &skipped_field.member;
+    let init = &skipped_field.init;
+    quote!(#member: #init)
+}
+
+fn merge_field(field: &syn::Field, field_attrs: &FieldAttrs) -> proc_macro2::TokenStream {
+    let field_name = &field.ident;
+    if is_collection(field, field_attrs) {
+        quote!(
+            if let std::option::Option::Some(items) = other.#field_name {
+                std::iter::Extend::extend(
+                    self.#field_name.get_or_insert_with(std::default::Default::default),
+                    items,
+                );
+            }
+        )
     } else {
-        quote!(#field_name: self.#field_name.clone().ok_or("field was not set")?)
+        quote!(
+            if other.#field_name.is_some() {
+                self.#field_name = other.#field_name;
+            }
+        )
     }
 }
 
-fn functionize_field(field: &syn::Field) -> proc_macro2::TokenStream {
+/// How the generated setters take and hand back the builder.
+#[derive(Clone, Copy)]
+enum SetterReceiver {
+    /// `&mut self` returning `&mut Self`, used by the regular builder.
+    Borrowed,
+    /// `mut self` returning `Self`, used by the typestate builder.
+    Owned,
+}
+
+impl SetterReceiver {
+    fn receiver(self) -> proc_macro2::TokenStream {
+        match self {
+            SetterReceiver::Borrowed => quote!(&mut self),
+            SetterReceiver::Owned => quote!(mut self),
+        }
+    }
+
+    fn return_type(self) -> proc_macro2::TokenStream {
+        match self {
+            SetterReceiver::Borrowed => quote!(&mut Self),
+            SetterReceiver::Owned => quote!(Self),
+        }
+    }
+}
+
+fn functionize_field(
+    field: &syn::Field,
+    field_attrs: &FieldAttrs,
+    receiver: SetterReceiver,
+) -> proc_macro2::TokenStream {
+    if field_attrs.setter.skip {
+        return quote!();
+    }
     let field_name = field.ident.as_ref().unwrap();
     let mut field_type = &field.ty;
-    if let std::option::Option::Some(inner_ty) = extract_inner_type(field_type, "Option") {
-        field_type = inner_ty;
-    }
-    let once_setter_tt = once_setter(field_name, field_type);
-    if field.attrs.is_empty() {
-        return quote!(
-           #once_setter_tt
-        );
+    let mut strip_option = false;
+    if let std::option::Option::Some(inner_ty) = option_inner_type(field, field_attrs) {
+        if field_attrs.setter.strip_option.unwrap_or(true) {
+            field_type = inner_ty;
+            strip_option = true;
+        }
     }
-    match extract_each_attr_value(&field.attrs) {
-        std::result::Result::Ok(attr_value) => {
-            let attr_value = &format_ident!("{}", attr_value);
-            let vec_inner_type =
-                extract_inner_type(field_type, "Vec").expect("inner type of Vec is <>????");
-            let each_setter_tt = each_setter(attr_value, field_name, vec_inner_type);
-            if attr_value == field_name {
+    // Stripped `Option` fields and regular fields are both stored as `Option<T>` in the builder,
+    // so the value needs wrapping, while unstripped `Option` fields take the `Option` as is.
+    let wrap_some = strip_option || option_inner_type(field, field_attrs).is_none();
+    let once_setter_tt = once_setter(field_name, field_type, field_attrs, wrap_some, receiver);
+    // `Option` fields can also be set from an `Option` and reset to `None`.
+    let option_setters_tt = if option_inner_type(field, field_attrs).is_some() {
+        let opt_setter_tt = if strip_option {
+            std::option::Option::Some(opt_setter(field_name, field_type, field_attrs, receiver))
+        } else {
+            std::option::Option::None
+        };
+        let clear_setter_tt = clear_setter(field_name, field_attrs, receiver);
+        std::option::Option::Some(quote!(
+            #opt_setter_tt
+            #clear_setter_tt
+        ))
+    } else {
+        std::option::Option::None
+    };
+    let setters_tt = match &field_attrs.each {
+        std::option::Option::Some(method_name) => {
+            let item_type: syn::Type =
+                syn::parse_quote!(<#field_type as std::iter::IntoIterator>::Item);
+            let each_setter_tt =
+                each_setter(method_name, field_name, &item_type, field_attrs, receiver);
+            let extend_setter_tt = extend_setter(field_name, &item_type, field_attrs, receiver);
+            if method_name == field_name {
                 quote!(
                     #each_setter_tt
+                    #extend_setter_tt
                 )
             } else {
                 quote! (
                     #once_setter_tt
                     #each_setter_tt
+                    #extend_setter_tt
                 )
             }
         }
-        std::result::Result::Err(err) => err,
+        std::option::Option::None => quote!(
+            #once_setter_tt
+        ),
+    };
+    quote!(
+        #setters_tt
+        #option_setters_tt
+    )
+}
+
+/// Build the setter argument type, wrapping it in `impl Into<_>` when requested.
+fn setter_arg_type(field_type: &syn::Type, setter: &SetterAttrs) -> proc_macro2::TokenStream {
+    if setter.into {
+        quote!(impl std::convert::Into<#field_type>)
+    } else {
+        quote!(#field_type)
+    }
+}
+
+/// Build the expression converting the setter argument into the field type.
+fn setter_value(field_name: &syn::Ident, setter: &SetterAttrs) -> proc_macro2::TokenStream {
+    if setter.into {
+        quote!(#field_name.into())
+    } else {
+        quote!(#field_name)
     }
 }
 
-fn once_setter(field_name: &syn::Ident, field_type: &syn::Type) -> proc_macro2::TokenStream {
-    quote!(fn #field_name(&mut self, #field_name: #field_type) -> &mut Self {
-        self.#field_name = std::option::Option::Some(#field_name);
+fn once_setter(
+    field_name: &syn::Ident,
+    field_type: &syn::Type,
+    field_attrs: &FieldAttrs,
+    wrap_some: bool,
+    receiver: SetterReceiver,
+) -> proc_macro2::TokenStream {
+    let setter = &field_attrs.setter;
+    let vis = &field_attrs.vis;
+    let method_name = setter.method_name(field_name);
+    let arg_type = setter_arg_type(field_type, setter);
+    let return_type = receiver.return_type();
+    let receiver = receiver.receiver();
+    let value = setter_value(field_name, setter);
+    let value = if wrap_some {
+        quote!(std::option::Option::Some(#value))
+    } else {
+        value
+    };
+    let docs = &field_attrs.docs;
+    quote!(#(#docs)* #vis fn #method_name(#receiver, #field_name: #arg_type) -> #return_type {
+        self.#field_name = #value;
         self
     })
 }
+
+/// Generate `<field>_opt`, setting a stripped `Option` field from an `Option` of its inner type.
+fn opt_setter(
+    field_name: &syn::Ident,
+    inner_type: &syn::Type,
+    field_attrs: &FieldAttrs,
+    receiver: SetterReceiver,
+) -> proc_macro2::TokenStream {
+    let setter = &field_attrs.setter;
+    let vis = &field_attrs.vis;
+    let method_name = setter.opt_method_name(field_name);
+    let arg_type = setter_arg_type(inner_type, setter);
+    let return_type = receiver.return_type();
+    let receiver = receiver.receiver();
+    let value = if setter.into {
+        quote!(std::option::Option::map(#field_name, std::convert::Into::into))
+    } else {
+        quote!(#field_name)
+    };
+    let docs = &field_attrs.docs;
+    quote!(
+        #(#docs)*
+        #vis fn #method_name(
+            #receiver,
+            #field_name: std::option::Option<#arg_type>,
+        ) -> #return_type {
+            self.#field_name = #value;
+            self
+        }
+    )
+}
+
+/// Generate `clear_<field>`, resetting an `Option` field to `None`.
+fn clear_setter(
+    field_name: &syn::Ident,
+    field_attrs: &FieldAttrs,
+    receiver: SetterReceiver,
+) -> proc_macro2::TokenStream {
+    let vis = &field_attrs.vis;
+    let method_name = field_attrs.setter.clear_method_name(field_name);
+    let return_type = receiver.return_type();
+    let receiver = receiver.receiver();
+    let doc = format!("Reset `{}` to `None`.", field_name);
+    quote!(
+        #[doc = #doc]
+        #vis fn #method_name(#receiver) -> #return_type {
+            self.#field_name = std::option::Option::None;
+            self
+        }
+    )
+}
+
 fn each_setter(
     method_name: &syn::Ident,
     field_name: &syn::Ident,
-    field_type: &syn::Type,
+    item_type: &syn::Type,
+    field_attrs: &FieldAttrs,
+    receiver: SetterReceiver,
 ) -> proc_macro2::TokenStream {
+    let setter = &field_attrs.setter;
+    let vis = &field_attrs.vis;
+    let arg_type = setter_arg_type(item_type, setter);
+    let return_type = receiver.return_type();
+    let receiver = receiver.receiver();
+    let value = setter_value(field_name, setter);
+    let docs = &field_attrs.docs;
     quote!(
-        fn #method_name(&mut self, #field_name: #field_type) -> &mut Self {
-            if let std::option::Option::Some(ref mut v) = self.#field_name {
-                v.push(#field_name);
-            } else {
-                self.#field_name = std::option::Option::Some(vec![#field_name]);
-            }
+        #(#docs)*
+        #vis fn #method_name(#receiver, #field_name: #arg_type) -> #return_type {
+            std::iter::Extend::extend(
+                self.#field_name.get_or_insert_with(std::default::Default::default),
+                std::option::Option::Some(#value),
+            );
             self
         }
     )
 }
 
-fn extract_each_attr_value(
-    attrs: &Vec<syn::Attribute>,
-) -> Result<String, proc_macro2::TokenStream> {
-    if let std::option::Option::Some(attr) = attrs.last() {
-        if let std::result::Result::Ok(syn::Meta::List(list)) = attr.parse_meta() {
-            if list.path.is_ident("builder") {
-                if let std::option::Option::Some(syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
-                    path,
-                    lit: syn::Lit::Str(lit_str),
-                    ..
-                }))) = list.nested.last()
-                {
-                    if path.is_ident("each") {
-                        return std::result::Result::Ok(lit_str.value());
-                    } else {
-                        println!("not each");
-                        return std::result::Result::Err(syn::Error::new_spanned(list, "expected `builder(each = \"...\")`")
-                            .to_compile_error());
-                    }
-                }
-            }
+/// Generate `extend_<field>`, appending every item of an iterator to a collection field.
+fn extend_setter(
+    field_name: &syn::Ident,
+    item_type: &syn::Type,
+    field_attrs: &FieldAttrs,
+    receiver: SetterReceiver,
+) -> proc_macro2::TokenStream {
+    let setter = &field_attrs.setter;
+    let vis = &field_attrs.vis;
+    let method_name = field_attrs.setter.extend_method_name(field_name);
+    let arg_type = setter_arg_type(item_type, setter);
+    let return_type = receiver.return_type();
+    let receiver = receiver.receiver();
+    let items = if setter.into {
+        quote!(std::iter::Iterator::map(
+            std::iter::IntoIterator::into_iter(#field_name),
+            std::convert::Into::into,
+        ))
+    } else {
+        quote!(#field_name)
+    };
+    let doc = format!("Append every item of `{}`.", field_name);
+    quote!(
+        #[doc = #doc]
+        #vis fn #method_name(
+            #receiver,
+            #field_name: impl std::iter::IntoIterator<Item = #arg_type>,
+        ) -> #return_type {
+            std::iter::Extend::extend(
+                self.#field_name.get_or_insert_with(std::default::Default::default),
+                #items,
+            );
+            self
+        }
+    )
+}
+
+/// Fields which must be set before `build` succeeds, or with `typestate`, becomes available.
+///
+/// Fields which are neither required nor an `Option` fall back to `Default::default()`.
+fn is_required(field: &syn::Field, field_attrs: &FieldAttrs) -> bool {
+    match field_attrs.required {
+        std::option::Option::Some(required) => required,
+        std::option::Option::None => {
+            option_inner_type(field, field_attrs).is_none()
+                && !is_collection(field, field_attrs)
+                && !field_attrs.setter.skip
+                && field_attrs.default_with.is_none()
         }
     }
-    unreachable!("you should've check for attrs emptyness before")
 }
 
-fn optionize_field(field: &syn::Field) -> proc_macro2::TokenStream {
-    let ref field_name = field.ident;
-    let ref field_type = field.ty;
-    if extract_inner_type(field_type, "Option").is_some() {
+/// The `T` of an `Option<T>` field, which is stored as is in the builder.
+fn option_inner_type<'f>(
+    field: &'f syn::Field,
+    field_attrs: &FieldAttrs,
+) -> std::option::Option<&'f syn::Type> {
+    if field_attrs.required == std::option::Option::Some(true) {
+        return std::option::Option::None;
+    }
+    extract_inner_type(&field.ty, "Option")
+}
+
+/// Whether the field is a collection, which starts out empty rather than unset in the builder.
+fn is_collection(field: &syn::Field, field_attrs: &FieldAttrs) -> bool {
+    field_attrs.each.is_some()
+        || (field_attrs.required != std::option::Option::Some(true)
+            && extract_inner_type(&field.ty, "Vec").is_some())
+}
+
+fn optionize_field(field: &syn::Field, field_attrs: &FieldAttrs) -> proc_macro2::TokenStream {
+    let field_name = &field.ident;
+    let field_type = &field.ty;
+    if option_inner_type(field, field_attrs).is_some() {
         quote!(#field_name: #field_type)
     } else {
         quote!(#field_name: std::option::Option<#field_type>)
     }
 }
 
-fn extract_inner_type<'t>(ty: &'t syn::Type, expected_ident: &str) -> Option<&'t syn::Type> {
-    if let syn::Type::Path(syn::TypePath {
-        path: syn::Path { segments, .. },
-        ..
-    }) = ty
-    {
-        if let std::option::Option::Some(syn::PathSegment {
-            ident,
-            arguments:
-                syn::PathArguments::AngleBracketed(syn::AngleBracketedGenericArguments { args, .. }),
-        }) = segments.last()
-        {
-            if ident == expected_ident {
-                if let std::option::Option::Some(syn::GenericArgument::Type(ty)) = args.last() {
-                    return std::option::Option::Some(ty);
-                }
-            }
+/// Paths under which the standard library exports the types recognized by `extract_inner_type`.
+const STD_PATHS: &[(&str, &[&str])] = &[
+    ("Option", &["std::option", "core::option"]),
+    ("Vec", &["std::vec", "alloc::vec"]),
+];
+
+/// The `T` of an `Option<T>` or `Vec<T>` type, given as `expected_ident`.
+///
+/// Besides the bare name, only the standard library paths in `STD_PATHS` are recognized, so that
+/// e.g. `my::Option<T>` isn't mistaken for an `Option`.
+fn extract_inner_type<'t>(
+    ty: &'t syn::Type,
+    expected_ident: &str,
+) -> std::option::Option<&'t syn::Type> {
+    let path = match ty {
+        syn::Type::Path(syn::TypePath {
+            qself: std::option::Option::None,
+            path,
+        }) => path,
+        _ => return std::option::Option::None,
+    };
+    let last = path.segments.last()?;
+    if last.ident != expected_ident {
+        return std::option::Option::None;
+    }
+    let modules = &path.segments.iter().collect::<Vec<_>>()[..path.segments.len() - 1];
+    if modules.is_empty() {
+        if path.leading_colon.is_some() {
+            return std::option::Option::None;
+        }
+    } else {
+        if modules.iter().any(|segment| !segment.arguments.is_empty()) {
+            return std::option::Option::None;
         }
+        let module = modules
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect::<Vec<_>>()
+            .join("::");
+        let (_, std_modules) = STD_PATHS
+            .iter()
+            .find(|(ident, _)| *ident == expected_ident)?;
+        if !std_modules.contains(&module.as_str()) {
+            return std::option::Option::None;
+        }
+    }
+    match &last.arguments {
+        syn::PathArguments::AngleBracketed(syn::AngleBracketedGenericArguments {
+            args, ..
+        }) if args.len() == 1 => match args.first() {
+            std::option::Option::Some(syn::GenericArgument::Type(ty)) => {
+                std::option::Option::Some(ty)
+            }
+            _ => std::option::Option::None,
+        },
+        _ => std::option::Option::None,
     }
-    None
 }
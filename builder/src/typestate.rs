@@ -0,0 +1,212 @@
+//! Builder flavour enabled by `#[builder(typestate)]`.
+//!
+//! Every required field gets a type parameter on the builder which is `()` while the field is
+//! unset and the field type once it has been set. Setters for required fields are only available
+//! while their parameter is `()`, and `build` is only available once all of them are set, so a
+//! missing field is reported by the compiler instead of at runtime.
+//!
+//! Since its type changes as fields get set, such a builder has no `merge` and can't be created
+//! from an existing value with `to_builder`. It has no `missing_fields` or field getters either,
+//! its type already telling which fields are set.
+
+use quote::{format_ident, quote};
+
+use crate::attrs::is_deserialize;
+use crate::{
+    builder_doc, functionize_field, initialize_field, is_required, option_inner_type,
+    optionize_field, setter_arg_type, setter_value, skip_field, SetterReceiver, StructAttrs,
+    Target, TargetField,
+};
+
+pub(crate) fn typestate_builder(
+    struct_ident: &syn::Ident,
+    target: &Target,
+    struct_attrs: &StructAttrs,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if let std::option::Option::Some(validate) = &struct_attrs.build_fn.validate {
+        return std::result::Result::Err(syn::Error::new_spanned(
+            validate,
+            "`build_fn(validate = \"...\")` cannot be combined with `typestate`",
+        ));
+    }
+    if let std::option::Option::Some(asyncness) = &struct_attrs.build_fn.asyncness {
+        return std::result::Result::Err(syn::Error::new_spanned(
+            asyncness,
+            "`build_fn(async)` cannot be combined with `typestate`",
+        ));
+    }
+    if let std::option::Option::Some(default_with) = target
+        .fields
+        .iter()
+        .find_map(|target_field| target_field.attrs.default_with.as_ref())
+    {
+        return std::result::Result::Err(syn::Error::new_spanned(
+            default_with,
+            "`default_with` cannot be combined with `typestate`",
+        ));
+    }
+    if let std::option::Option::Some(deserialize) = struct_attrs
+        .derives
+        .iter()
+        .find(|path| is_deserialize(path))
+    {
+        return std::result::Result::Err(syn::Error::new_spanned(
+            deserialize,
+            "`derive(Deserialize)` cannot be combined with `typestate`",
+        ));
+    }
+
+    let builder_struct_ident = &target.builder_ident;
+    let builder_fn = &target.builder_fn;
+    let target_path = &target.path;
+    let vis = struct_attrs.vis();
+    let derives = struct_attrs.derive_attr();
+    let doc = builder_doc(struct_ident, target);
+    let (required, optional): (Vec<_>, Vec<_>) = target
+        .fields
+        .iter()
+        .partition(|target_field| is_required(&target_field.field, &target_field.attrs));
+    let params = (0..required.len())
+        .map(|idx| format_ident!("__T{}", idx))
+        .collect::<Vec<_>>();
+    let unset = required.iter().map(|_| quote!(()));
+    let set = required.iter().map(|target_field| &target_field.field.ty);
+
+    let required_members = required.iter().map(|target_field| &target_field.member);
+    let required_names = required
+        .iter()
+        .map(|target_field| &target_field.field.ident)
+        .collect::<Vec<_>>();
+    let optional_names = optional
+        .iter()
+        .map(|target_field| &target_field.field.ident)
+        .collect::<Vec<_>>();
+    let optional_fields = optional
+        .iter()
+        .map(|target_field| optionize_field(&target_field.field, &target_field.attrs));
+    let optional_inits = optional
+        .iter()
+        .map(|target_field| initialize_field(&target_field.field, &target_field.attrs));
+    let optional_assigns = optional.iter().map(|target_field| {
+        let member = &target_field.member;
+        let field_name = &target_field.field.ident;
+        if option_inner_type(&target_field.field, &target_field.attrs).is_some() {
+            quote!(#member: self.#field_name)
+        } else {
+            quote!(#member: self.#field_name.unwrap_or_default())
+        }
+    });
+    let skipped_fields = target.skipped.iter().map(skip_field);
+    let optional_setters = optional.iter().map(|target_field| {
+        functionize_field(
+            &target_field.field,
+            &target_field.attrs,
+            SetterReceiver::Owned,
+        )
+    });
+    let required_setters = required.iter().enumerate().map(|(idx, target_field)| {
+        required_setter(
+            builder_struct_ident,
+            &required,
+            &optional_names,
+            &params,
+            idx,
+            target_field,
+        )
+    });
+
+    let builder_fn_doc = format!("Create a [`{}`] with no field set.", builder_struct_ident);
+    std::result::Result::Ok(quote!(
+        impl #struct_ident {
+            #[doc = #builder_fn_doc]
+            #vis fn #builder_fn() -> #builder_struct_ident<#(#unset),*> {
+                #builder_struct_ident {
+                    #(#required_names: (),)*
+                    #(#optional_inits),*
+                }
+            }
+        }
+
+        #doc
+        #derives
+        #vis struct #builder_struct_ident<#(#params),*> {
+            #(#required_names: #params,)*
+            #(#optional_fields),*
+        }
+
+        impl<#(#params),*> #builder_struct_ident<#(#params),*> {
+            #(#optional_setters)*
+        }
+
+        #(#required_setters)*
+
+        impl #builder_struct_ident<#(#set),*> {
+            /// Build the value, available once every required field is set.
+            #vis fn build(self) -> #struct_ident {
+                #target_path {
+                    #(#required_members: self.#required_names,)*
+                    #(#optional_assigns,)*
+                    #(#skipped_fields),*
+                }
+            }
+        }
+    ))
+}
+
+/// Generate the setter moving the builder from the state where the `idx`-th required field is
+/// unset into the one where it is set.
+fn required_setter(
+    builder_struct_ident: &syn::Ident,
+    required: &[&TargetField],
+    optional_names: &[&std::option::Option<syn::Ident>],
+    params: &[syn::Ident],
+    idx: usize,
+    target_field: &TargetField,
+) -> proc_macro2::TokenStream {
+    let field_attrs = &target_field.attrs;
+    let vis = &field_attrs.vis;
+    let field_name = target_field.field.ident.as_ref().unwrap();
+    let method_name = field_attrs.setter.method_name(field_name);
+    let field_type = &target_field.field.ty;
+    let arg_type = setter_arg_type(field_type, &field_attrs.setter);
+    let value = setter_value(field_name, &field_attrs.setter);
+    let docs = &field_attrs.docs;
+
+    let impl_params = params
+        .iter()
+        .enumerate()
+        .filter(|(param_idx, _)| *param_idx != idx)
+        .map(|(_, param)| param);
+    let unset_params = params.iter().enumerate().map(|(param_idx, param)| {
+        if param_idx == idx {
+            quote!(())
+        } else {
+            quote!(#param)
+        }
+    });
+    let set_params = params.iter().enumerate().map(|(param_idx, param)| {
+        if param_idx == idx {
+            quote!(#field_type)
+        } else {
+            quote!(#param)
+        }
+    });
+    let other_names = required
+        .iter()
+        .enumerate()
+        .filter(|(field_idx, _)| *field_idx != idx)
+        .map(|(_, target_field)| &target_field.field.ident);
+
+    quote!(
+        impl<#(#impl_params),*> #builder_struct_ident<#(#unset_params),*> {
+            #(#docs)*
+            #vis fn #method_name(self, #field_name: #arg_type) -> #builder_struct_ident<#(#set_params),*> {
+                #builder_struct_ident {
+                    #field_name: #value,
+                    #(#other_names: self.#other_names,)*
+                    #(#optional_names: self.#optional_names),*
+                }
+            }
+        }
+    )
+}
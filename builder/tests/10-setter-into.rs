@@ -0,0 +1,48 @@
+// Passing owned values to every setter gets noisy when the caller mostly has
+// string slices or paths lying around. Fields annotated with
+// #[builder(setter(into))] should get setters accepting anything convertible
+// into the field type.
+//
+//     impl CommandBuilder {
+//         fn executable(&mut self, executable: impl Into<String>) -> &mut Self {
+//             self.executable = Some(executable.into());
+//             self
+//         }
+//     }
+//
+// Optional fields keep taking the inner type by default, but the unwrapping can
+// be spelled out with #[builder(setter(strip_option))] or turned off with
+// #[builder(setter(strip_option = false))], in which case the setter takes the
+// whole Option and can be used to reset the field to None.
+
+use derive_builder::Builder;
+use std::path::PathBuf;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(setter(into))]
+    executable: String,
+    #[builder(each = "arg", setter(into))]
+    args: Vec<String>,
+    #[builder(setter(into, strip_option))]
+    current_dir: Option<PathBuf>,
+    #[builder(setter(strip_option = false))]
+    stdin: Option<String>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo")
+        .arg("build")
+        .arg(String::from("--release"))
+        .current_dir("/tmp")
+        .stdin(Some("input".to_owned()))
+        .stdin(None)
+        .build()
+        .unwrap();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.current_dir, Some(PathBuf::from("/tmp")));
+    assert_eq!(command.stdin, None);
+}
@@ -0,0 +1,35 @@
+// Some invariants span several fields and can only be checked once the builder
+// is fully populated. A struct-level #[builder(build_fn(validate = "..."))]
+// names a function that receives the builder right before the struct is
+// constructed; any error it returns is propagated out of build().
+//
+//     impl RangeBuilder {
+//         pub fn build(&mut self) -> Result<Range, Box<dyn Error>> {
+//             validate_range(self)?;
+//             Ok(Range { ... })
+//         }
+//     }
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(build_fn(validate = "validate_range"))]
+pub struct Range {
+    min: u32,
+    max: u32,
+}
+
+fn validate_range(builder: &RangeBuilder) -> Result<(), String> {
+    match (builder.min, builder.max) {
+        (Some(min), Some(max)) if min > max => Err(format!("min ({}) > max ({})", min, max)),
+        _ => Ok(()),
+    }
+}
+
+fn main() {
+    let range = Range::builder().min(1).max(10).build().unwrap();
+    assert_eq!((range.min, range.max), (1, 10));
+
+    let err = Range::builder().min(10).max(1).build().err().unwrap();
+    assert_eq!(err.to_string(), "min (10) > max (1)");
+}
@@ -0,0 +1,46 @@
+// Forgetting to set a required field is only caught at runtime by the regular
+// builder. With #[builder(typestate)] the builder instead carries one type
+// parameter per required field, which is () until that field has been set.
+// build() only exists once every required field is set and returns the struct
+// directly.
+//
+//     pub struct CommandBuilder<T0, T1> {
+//         executable: T0,
+//         current_dir: T1,
+//         args: Option<Vec<String>>,
+//     }
+//
+//     impl<T1> CommandBuilder<(), T1> {
+//         fn executable(self, executable: String) -> CommandBuilder<String, T1> {...}
+//     }
+//
+//     impl CommandBuilder<String, String> {
+//         pub fn build(self) -> Command {...}
+//     }
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command {
+    #[builder(setter(into))]
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: String,
+    stdin: Option<String>,
+}
+
+fn main() {
+    let command: Command = Command::builder()
+        .current_dir("..".to_owned())
+        .arg("build".to_owned())
+        .executable("cargo")
+        .arg("--release".to_owned())
+        .build();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.current_dir, "..");
+    assert!(command.stdin.is_none());
+}
@@ -0,0 +1,17 @@
+// With #[builder(typestate)] a missing required field is a compile error
+// instead of an Err returned from build().
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command {
+    executable: String,
+    current_dir: String,
+}
+
+fn main() {
+    let _command = Command::builder()
+        .executable("cargo".to_owned())
+        .build();
+}
@@ -0,0 +1,49 @@
+// #[builder(each = "...")] is not limited to Vec. Any collection implementing
+// Default and Extend gets a one-at-a-time setter taking the collection's
+// IntoIterator::Item, which means (K, V) pairs for maps. Every such field also
+// gets an extend_<field> method appending a whole iterator at once.
+//
+//     impl RequestBuilder {
+//         fn header(&mut self, header: (String, String)) -> &mut Self {...}
+//         fn extend_headers(
+//             &mut self,
+//             headers: impl IntoIterator<Item = (String, String)>,
+//         ) -> &mut Self {...}
+//     }
+
+use derive_builder::Builder;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+#[derive(Builder)]
+pub struct Request {
+    #[builder(each = "header")]
+    headers: HashMap<String, String>,
+    #[builder(each = "tag", setter(into))]
+    tags: HashSet<String>,
+    #[builder(each = "query")]
+    query: BTreeMap<String, u32>,
+    #[builder(each = "step")]
+    steps: VecDeque<u8>,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+}
+
+fn main() {
+    let request = Request::builder()
+        .header(("Accept".to_owned(), "*/*".to_owned()))
+        .extend_headers(vec![("Host".to_owned(), "localhost".to_owned())])
+        .tag("a")
+        .extend_tags(vec!["b", "a"])
+        .query(("page".to_owned(), 2))
+        .extend_steps(1..=3)
+        .step(4)
+        .build()
+        .unwrap();
+
+    assert_eq!(request.headers.len(), 2);
+    assert_eq!(request.headers["Host"], "localhost");
+    assert_eq!(request.tags.len(), 2);
+    assert_eq!(request.query["page"], 2);
+    assert_eq!(request.steps, vec![1, 2, 3, 4]);
+    assert!(request.args.is_empty());
+}
@@ -0,0 +1,53 @@
+// The shape of the generated builder can be adjusted to fit the API it is part
+// of with struct-level options:
+//
+//   - name = "..." renames the builder type,
+//   - vis = "..." sets the visibility of the builder type, builder(), build()
+//     and the setters (pub by default),
+//   - derive(...) derives traits on the builder type,
+//   - setter(prefix = "...") prepends a prefix to every setter name, like
+//     with_host, but also with_timeout_opt and with_clear_timeout.
+//
+// A field-level vis = "..." overrides the visibility of that field's setters.
+
+mod config {
+    use derive_builder::Builder;
+
+    #[derive(Builder)]
+    #[builder(
+        name = "ConfigFactory",
+        vis = "pub(crate)",
+        derive(Debug, Clone),
+        setter(prefix = "with_", into)
+    )]
+    pub struct Config {
+        pub host: String,
+        pub port: u16,
+        #[builder(vis = "")]
+        pub secret: Option<String>,
+        pub timeout: Option<u32>,
+    }
+
+    pub(crate) fn with_default_secret(factory: &mut ConfigFactory) -> &mut ConfigFactory {
+        factory.with_secret("hunter2")
+    }
+}
+
+fn main() {
+    let mut factory: config::ConfigFactory = config::Config::builder();
+    factory.with_host("localhost").with_port(8080u16);
+    config::with_default_secret(&mut factory);
+
+    let debug = format!("{:?}", factory);
+    assert!(debug.starts_with("ConfigFactory {"));
+
+    let config = factory.clone().build().unwrap();
+    assert_eq!(config.host, "localhost");
+    assert_eq!(config.port, 8080);
+    assert_eq!(config.secret.as_deref(), Some("hunter2"));
+
+    factory.with_timeout_opt(Some(30u32));
+    assert_eq!(factory.clone().build().unwrap().timeout, Some(30));
+    factory.with_clear_timeout();
+    assert_eq!(factory.build().unwrap().timeout, None);
+}
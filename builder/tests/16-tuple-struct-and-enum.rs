@@ -0,0 +1,67 @@
+// Tuple structs get positional setters named _0, _1, ... unless a field is
+// given a name with #[builder(name = "...")].
+//
+// Enums get one builder per variant, named <Enum><Variant>Builder and created
+// through <variant>_builder() on the enum, with runs of capitals in the
+// variant name kept together as one word. A variant's builder can be renamed
+// with #[builder(name = "...")] on the variant.
+//
+//     impl Shape {
+//         pub fn circle_builder() -> ShapeCircleBuilder {...}
+//         pub fn rect_builder() -> ShapeRectBuilder {...}
+//         pub fn empty_builder() -> ShapeEmptyBuilder {...}
+//         pub fn svg_path_builder() -> ShapeSVGPathBuilder {...}
+//         pub fn text_builder() -> Caption {...}
+//     }
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Point(u32, #[builder(name = "y")] u32, Option<String>);
+
+#[derive(Builder, Debug, PartialEq)]
+pub enum Shape {
+    Circle {
+        radius: u32,
+        label: Option<String>,
+    },
+    Rect(u32, #[builder(name = "height")] u32),
+    Empty,
+    SVGPath(String),
+    #[builder(name = "Caption")]
+    Text(String),
+}
+
+fn main() {
+    let point = Point::builder()._0(1).y(2).build().unwrap();
+    assert_eq!((point.0, point.1, point.2), (1, 2, None));
+
+    let circle = Shape::circle_builder().radius(3).build().unwrap();
+    assert_eq!(
+        circle,
+        Shape::Circle {
+            radius: 3,
+            label: None,
+        }
+    );
+
+    let rect = Shape::rect_builder()._0(4).height(5).build().unwrap();
+    assert_eq!(rect, Shape::Rect(4, 5));
+
+    let empty = Shape::empty_builder().build().unwrap();
+    assert_eq!(empty, Shape::Empty);
+
+    let path = Shape::svg_path_builder()
+        ._0("M0 0".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(path, Shape::SVGPath("M0 0".to_owned()));
+
+    let mut caption: Caption = Shape::text_builder();
+    assert_eq!(
+        caption._0("hi".to_owned()).build().unwrap(),
+        Shape::Text("hi".to_owned())
+    );
+
+    assert!(Shape::rect_builder()._0(4).build().is_err());
+}
@@ -0,0 +1,15 @@
+// Shapes which have nothing to build, like unit structs and unions, are
+// rejected with an error pointing at the offending item rather than a panic.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Marker;
+
+#[derive(Builder)]
+pub union Bits {
+    int: u32,
+    float: f32,
+}
+
+fn main() {}
@@ -0,0 +1,53 @@
+// Configuration is often assembled in layers: defaults, then a config file,
+// then the environment, then the command line. Each layer can be expressed as
+// a partially populated builder and merged on top of the previous one.
+//
+//     impl ServerBuilder {
+//         pub fn merge(&mut self, other: ServerBuilder) -> &mut Self {...}
+//     }
+//
+// Fields set in `other` replace the ones in `self`, collection fields are
+// extended instead. An existing value can be turned back into a builder with
+// every field set, through `From<&Server>` or `Server::to_builder`.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Clone, Debug, PartialEq)]
+pub struct Server {
+    host: String,
+    port: u16,
+    log_file: Option<String>,
+    #[builder(each = "listener")]
+    listeners: Vec<String>,
+}
+
+fn main() {
+    let mut defaults = Server::builder();
+    defaults.host("localhost".to_owned()).port(80).listener("http".to_owned());
+
+    let mut file = Server::builder();
+    file.port(8080).log_file("server.log".to_owned());
+
+    let mut cli = Server::builder();
+    cli.listener("https".to_owned());
+
+    let server = defaults.merge(file).merge(cli).build().unwrap();
+    assert_eq!(
+        server,
+        Server {
+            host: "localhost".to_owned(),
+            port: 8080,
+            log_file: Some("server.log".to_owned()),
+            listeners: vec!["http".to_owned(), "https".to_owned()],
+        }
+    );
+
+    let mut copy = server.to_builder();
+    copy.port(443);
+    let copy = copy.build().unwrap();
+    assert_eq!(copy.port, 443);
+    assert_eq!(copy.host, server.host);
+
+    let same = ServerBuilder::from(&server).build().unwrap();
+    assert_eq!(same, server);
+}
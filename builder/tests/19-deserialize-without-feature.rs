@@ -0,0 +1,12 @@
+// Deriving Deserialize for the builder is only available with the `serde`
+// feature of derive_builder.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(derive(Deserialize))]
+pub struct Command {
+    executable: String,
+}
+
+fn main() {}
@@ -0,0 +1,31 @@
+// With the `serde` feature enabled, #[builder(derive(Deserialize))] makes the
+// builder deserializable. Every field of the builder is optional, so a config
+// file only has to contain some of them and the rest can be filled in from
+// code before calling build().
+//
+// Fields missing from the input are left as in a fresh builder, which means
+// collections start out empty.
+
+use derive_builder::Builder;
+use serde::Deserialize;
+
+#[derive(Builder)]
+#[builder(derive(Deserialize))]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    env: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let mut builder: CommandBuilder =
+        serde_json::from_str(r#"{ "executable": "cargo", "args": ["build"] }"#).unwrap();
+    let command = builder.arg("--release".to_owned()).build().unwrap();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert!(command.env.is_empty());
+    assert!(command.current_dir.is_none());
+}
@@ -0,0 +1,43 @@
+// Some fields are internal state that callers must never set.
+//
+// #[builder(skip)] leaves the field out of the builder entirely. build()
+// initializes it with Default::default(), or with the given expression when
+// written as #[builder(skip = "...")].
+//
+// #[builder(setter(skip))] keeps the field in the builder, so that it can
+// still be merged in or validated, but generates no setter for it. Unless set
+// some other way, build() falls back to Default::default().
+
+use derive_builder::Builder;
+use std::collections::HashMap;
+
+#[derive(Builder)]
+pub struct Client {
+    url: String,
+    #[builder(skip)]
+    cache: HashMap<String, Vec<u8>>,
+    #[builder(skip = "3")]
+    retries: u32,
+    #[builder(setter(skip))]
+    requests_sent: u64,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Counter(String, #[builder(skip = "1")] u64);
+
+fn main() {
+    let client = Client::builder()
+        .url("http://localhost".to_owned())
+        .build()
+        .unwrap();
+
+    assert_eq!(client.url, "http://localhost");
+    assert!(client.cache.is_empty());
+    assert_eq!(client.retries, 3);
+    assert_eq!(client.requests_sent, 0);
+
+    let counter = Counter::builder()._0("visits".to_owned()).build();
+    assert_eq!(counter.0, "visits");
+    assert_eq!(counter.1, 1);
+}
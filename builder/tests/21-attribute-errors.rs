@@ -0,0 +1,39 @@
+// Mistakes inside `#[builder(...)]` are all reported at once, each pointing at
+// the option at fault: unknown options, options given twice, values of the
+// wrong kind and options which contradict each other. Attributes belonging to
+// other macros are left alone.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(setter(into, prefix = 3), typestate = "yes")]
+#[builder(nme = "CommandMaker")]
+pub struct Command {
+    executable: String,
+}
+
+#[derive(Builder)]
+pub struct Job {
+    #[allow(dead_code)]
+    #[builder(setter(into, intoo))]
+    executable: String,
+    #[builder(each = "arg")]
+    #[builder(each = "argument")]
+    args: Vec<String>,
+    #[builder(vis)]
+    env: Vec<String>,
+    #[builder(skip, each = "dir")]
+    current_dir: Option<String>,
+    #[builder("arg")]
+    verbose: bool,
+    #[builder(default_with_async = "load_timeout")]
+    timeout: u64,
+}
+
+#[derive(Builder)]
+pub enum Step {
+    #[builder(bogus)]
+    Run { command: String },
+}
+
+fn main() {}
@@ -0,0 +1,101 @@
+// A field-level #[builder(default_with = "...")] names a function which build()
+// calls for a value when the field was never set. The function returns either
+// the field type or a Result of it, in which case an error is propagated out of
+// build().
+//
+// With a struct-level #[builder(build_fn(async))], build() becomes an async fn
+// consuming the builder, and the setters take and return the builder by value.
+// Its defaults can then also come from async functions, given with
+// #[builder(default_with_async = "...")] and awaited, next to plain ones.
+//
+//     impl PoolBuilder {
+//         pub async fn build(self) -> Result<Pool, Box<dyn Error>> {
+//             Ok(Pool {
+//                 host: match self.host.clone() {
+//                     Some(value) => value,
+//                     None => resolve_host().await?,
+//                 },
+//                 timeout: match self.timeout.clone() {
+//                     Some(value) => value,
+//                     None => default_timeout()?,
+//                 },
+//                 ...
+//             })
+//         }
+//     }
+
+use derive_builder::Builder;
+use std::future::Future;
+use std::task::{Context, Poll, Waker};
+
+#[derive(Builder)]
+pub struct Limits {
+    #[builder(default_with = "default_max")]
+    max: u32,
+    #[builder(default_with = "env_min")]
+    min: u32,
+    #[builder(default_with = "default_label")]
+    label: Option<String>,
+}
+
+fn default_max() -> u32 {
+    100
+}
+
+fn env_min() -> Result<u32, String> {
+    Err("MIN is not configured".to_owned())
+}
+
+fn default_label() -> Option<String> {
+    Some("limits".to_owned())
+}
+
+#[derive(Builder)]
+#[builder(build_fn(async))]
+pub struct Pool {
+    #[builder(default_with_async = "resolve_host")]
+    host: String,
+    #[builder(default_with = "default_timeout")]
+    timeout: u64,
+    size: usize,
+}
+
+fn default_timeout() -> u64 {
+    30
+}
+
+async fn resolve_host() -> Result<String, std::io::Error> {
+    Ok("10.0.0.1".to_owned())
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+fn main() {
+    let limits = Limits::builder().min(1).build().unwrap();
+    assert_eq!((limits.min, limits.max), (1, 100));
+    assert_eq!(limits.label.as_deref(), Some("limits"));
+
+    let limits = Limits::builder().min(1).max(5).label("set".to_owned()).build().unwrap();
+    assert_eq!((limits.min, limits.max), (1, 5));
+    assert_eq!(limits.label.as_deref(), Some("set"));
+
+    let err = Limits::builder().build().err().unwrap();
+    assert_eq!(err.to_string(), "MIN is not configured");
+
+    let pool = block_on(Pool::builder().size(4).build()).unwrap();
+    assert_eq!((pool.host.as_str(), pool.size), ("10.0.0.1", 4));
+    assert_eq!(pool.timeout, 30);
+
+    let pool = block_on(Pool::builder().host("db".to_owned()).size(2).build()).unwrap();
+    assert_eq!(pool.host, "db");
+
+    assert!(block_on(Pool::builder().build()).is_err());
+}
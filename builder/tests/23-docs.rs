@@ -0,0 +1,37 @@
+// The `///` comments of a field are copied onto its setters, and the builder
+// type and its build() method are documented with the fields which have to be
+// set, so the generated API passes #![deny(missing_docs)].
+//
+//     /// Builder for [`Command`], created by [`Command::builder`].
+//     ///
+//     /// Required fields: `executable`.
+//     ///
+//     /// Optional fields: `args`, `current_dir`.
+//     pub struct CommandBuilder { ... }
+
+#![deny(missing_docs)]
+//! Documented builder.
+
+use derive_builder::Builder;
+
+/// A program to run.
+#[derive(Builder)]
+pub struct Command {
+    /// Path of the program.
+    pub executable: String,
+    /// Arguments passed to the program.
+    #[builder(each = "arg")]
+    pub args: Vec<String>,
+    /// Directory to run the program in.
+    pub current_dir: Option<String>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.args, vec!["build"]);
+    assert_eq!(command.current_dir, None);
+}
@@ -0,0 +1,52 @@
+// Option fields get two more methods next to their setter: `<field>_opt`
+// taking the Option itself, for populating the builder conditionally, and
+// `clear_<field>` resetting the field to None, for reusing a builder.
+//
+//     impl CommandBuilder {
+//         pub fn current_dir_opt(&mut self, current_dir: Option<String>) -> &mut Self {
+//             self.current_dir = current_dir;
+//             self
+//         }
+//
+//         pub fn clear_current_dir(&mut self) -> &mut Self {
+//             self.current_dir = None;
+//             self
+//         }
+//     }
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    current_dir: Option<String>,
+    #[builder(setter(into))]
+    user: Option<String>,
+    #[builder(setter(strip_option = false))]
+    umask: Option<u32>,
+}
+
+fn main() {
+    let mut builder = Command::builder();
+    builder
+        .executable("cargo".to_owned())
+        .current_dir_opt(std::env::var("CARGO_TARGET_DIR").ok())
+        .user_opt(Some("root"))
+        .umask(Some(0o022));
+
+    let command = builder.current_dir("/".to_owned()).build().unwrap();
+    assert_eq!(command.current_dir.as_deref(), Some("/"));
+    assert_eq!(command.user.as_deref(), Some("root"));
+    assert_eq!(command.umask, Some(0o022));
+
+    let command = builder
+        .clear_current_dir()
+        .clear_user()
+        .clear_umask()
+        .build()
+        .unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.current_dir, None);
+    assert_eq!(command.user, None);
+    assert_eq!(command.umask, None);
+}
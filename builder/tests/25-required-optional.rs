@@ -0,0 +1,52 @@
+// Option and Vec fields are recognized by their name, or by their full path in
+// the standard library such as std::option::Option, but not through a path to
+// some other type named Option, nor through a type alias. Whenever the
+// heuristic gets it wrong, #[builder(required)] makes build() fail on an unset
+// field, while #[builder(optional)] lets it fall back to Default::default().
+
+use derive_builder::Builder;
+
+mod units {
+    // Not the standard Option.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Option<T>(pub T);
+}
+
+type Names = Vec<String>;
+
+#[derive(Builder)]
+pub struct Config {
+    name: std::option::Option<String>,
+    timeout: core::option::Option<u64>,
+    port: units::Option<u16>,
+    #[builder(optional)]
+    aliases: Names,
+    #[builder(required)]
+    retries: Option<u8>,
+    hosts: std::vec::Vec<String>,
+}
+
+fn main() {
+    let config = Config::builder()
+        .name("server".to_owned())
+        .port(units::Option(80))
+        .retries(Some(3))
+        .build()
+        .unwrap();
+    assert_eq!(config.name.as_deref(), Some("server"));
+    assert_eq!(config.timeout, None);
+    assert_eq!(config.port, units::Option(80));
+    assert!(config.aliases.is_empty());
+    assert_eq!(config.retries, Some(3));
+    assert!(config.hosts.is_empty());
+
+    let err = Config::builder()
+        .port(units::Option(80))
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err.to_string(), "field was not set");
+
+    let err = Config::builder().retries(None).build().err().unwrap();
+    assert_eq!(err.to_string(), "field was not set");
+}
@@ -0,0 +1,37 @@
+// The builder can be inspected before calling build(): `<field>_ref` returns
+// the value set so far, if any, and `missing_fields` lists the required
+// fields still unset, `is_complete` telling whether there are none.
+//
+//     impl CommandBuilder {
+//         pub fn executable_ref(&self) -> Option<&String> { ... }
+//         pub fn current_dir_ref(&self) -> Option<&String> { ... }
+//         pub fn missing_fields(&self) -> Vec<&'static str> { ... }
+//         pub fn is_complete(&self) -> bool { ... }
+//     }
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    args: Vec<String>,
+    env: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let mut builder = Command::builder();
+    assert_eq!(builder.executable_ref(), None);
+    assert_eq!(builder.args_ref(), Some(&Vec::new()));
+    assert_eq!(builder.missing_fields(), vec!["executable"]);
+    assert!(!builder.is_complete());
+
+    builder.executable("cargo".to_owned()).current_dir("..".to_owned());
+    assert_eq!(builder.executable_ref().map(String::as_str), Some("cargo"));
+    assert_eq!(builder.current_dir_ref().map(String::as_str), Some(".."));
+    assert!(builder.missing_fields().is_empty());
+    assert!(builder.is_complete());
+
+    let command = builder.build().unwrap();
+    assert_eq!(command.executable, "cargo");
+}
@@ -0,0 +1,38 @@
+// The builder has methods of its own, build, merge, missing_fields and
+// is_complete, and generates more for every field, like clear_<field> for an
+// Option field. A field whose generated methods take one of these names is
+// reported, pointing at the ways to rename its setters.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Branch {
+    name: String,
+    merge: bool,
+}
+
+#[derive(Builder)]
+pub struct Release {
+    #[builder(setter(prefix = "with_"))]
+    merge: bool,
+    #[builder(each = "merge")]
+    merges: Vec<String>,
+}
+
+#[derive(Builder)]
+pub struct Upload {
+    is_complete: bool,
+}
+
+#[derive(Builder)]
+pub struct Job {
+    build: u32,
+}
+
+#[derive(Builder)]
+pub struct Point {
+    x: Option<u8>,
+    clear_x: bool,
+}
+
+fn main() {}
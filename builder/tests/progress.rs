@@ -10,4 +10,25 @@ fn tests() {
     t.pass("tests/07-repeated-field.rs");
     t.compile_fail("tests/08-unrecognized-attribute.rs");
     t.pass("tests/09-redefined-prelude-types.rs");
+    t.pass("tests/10-setter-into.rs");
+    t.pass("tests/11-validate.rs");
+    t.pass("tests/12-typestate.rs");
+    t.compile_fail("tests/13-typestate-missing-field.rs");
+    t.pass("tests/14-collection-setters.rs");
+    t.pass("tests/15-builder-naming.rs");
+    t.pass("tests/16-tuple-struct-and-enum.rs");
+    t.compile_fail("tests/17-unsupported-shapes.rs");
+    t.pass("tests/18-merge.rs");
+    #[cfg(feature = "serde")]
+    t.pass("tests/19-deserialize.rs");
+    #[cfg(not(feature = "serde"))]
+    t.compile_fail("tests/19-deserialize-without-feature.rs");
+    t.pass("tests/20-skip.rs");
+    t.compile_fail("tests/21-attribute-errors.rs");
+    t.pass("tests/22-default-with.rs");
+    t.pass("tests/23-docs.rs");
+    t.pass("tests/24-option-setters.rs");
+    t.pass("tests/25-required-optional.rs");
+    t.pass("tests/26-inspect-builder.rs");
+    t.compile_fail("tests/27-method-clash.rs");
 }
@@ -0,0 +1,266 @@
+use quote::{format_ident, quote};
+
+use crate::{add_fmt_bound, field_bindings, fields_pattern};
+
+/// Implement `Display` by writing the `#[display("...")]` template of the struct, or of the
+/// variant `self` matches, with the fields it names filled in.
+pub(crate) fn expand(input: &syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+    let mut generics = input.generics.clone();
+    let body = match &input.data {
+        syn::Data::Struct(data_struct) => {
+            let arm = display_arm(
+                quote!(#ident),
+                ident,
+                &input.attrs,
+                &data_struct.fields,
+                &mut generics,
+            )?;
+            quote!(match self { #arm })
+        }
+        syn::Data::Enum(data_enum) if data_enum.variants.is_empty() => quote!(match *self {}),
+        syn::Data::Enum(data_enum) => {
+            let arms = data_enum
+                .variants
+                .iter()
+                .map(|variant| {
+                    let variant_ident = &variant.ident;
+                    display_arm(
+                        quote!(#ident::#variant_ident),
+                        variant_ident,
+                        &variant.attrs,
+                        &variant.fields,
+                        &mut generics,
+                    )
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            quote!(match self { #(#arms)* })
+        }
+        syn::Data::Union(syn::DataUnion { union_token, .. }) => {
+            return syn::Result::Err(syn::Error::new_spanned(
+                union_token,
+                "CustomDisplay is not implemented for unions",
+            ))
+        }
+    };
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    syn::Result::Ok(quote!(
+        impl #impl_generics std::fmt::Display for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                #body
+            }
+        }
+    ))
+}
+
+/// Build the match arm writing the struct or variant at `path`, and bound the fields its
+/// template prints.
+fn display_arm(
+    path: proc_macro2::TokenStream,
+    ident: &syn::Ident,
+    attrs: &[syn::Attribute],
+    fields: &syn::Fields,
+    generics: &mut syn::Generics,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let bindings = field_bindings(fields);
+    let pattern = fields_pattern(path, fields, &bindings);
+    let template = match parse_template_attr(attrs)? {
+        std::option::Option::Some(template) => template,
+        // Without fields there is nothing to lay out but the name.
+        std::option::Option::None if fields.is_empty() => {
+            let ident_str = format!("{}", ident);
+            return syn::Result::Ok(quote!(#pattern => f.write_str(#ident_str),));
+        }
+        std::option::Option::None => {
+            return syn::Result::Err(syn::Error::new_spanned(
+                ident,
+                "expected a `#[display(\"...\")]` template",
+            ))
+        }
+    };
+
+    let (format, args) = rewrite_template(&template, fields)?;
+    let mut used = Vec::new();
+    let mut bounded_fields = std::collections::BTreeMap::<String, Vec<&syn::Field>>::new();
+    for (idx, fmt_trait) in args {
+        if !used.contains(&idx) {
+            used.push(idx);
+        }
+        // Widths and precisions are `usize` values rather than formatted through a trait.
+        if let std::option::Option::Some(fmt_trait) = fmt_trait {
+            bounded_fields
+                .entry(fmt_trait.to_owned())
+                .or_default()
+                .push(fields.iter().nth(idx).unwrap());
+        }
+    }
+    for (fmt_trait, bounded_fields) in bounded_fields {
+        *generics = add_fmt_bound(
+            &bounded_fields,
+            generics.clone(),
+            &format_ident!("{}", fmt_trait),
+        );
+    }
+
+    let format = syn::LitStr::new(&format, template.span());
+    let used = used.into_iter().map(|idx| &bindings[idx]);
+    let used_values = used.clone();
+    syn::Result::Ok(quote!(
+        #pattern => std::write!(f, #format, #(#used = #used_values),*),
+    ))
+}
+
+/// Find the template among the `#[display("...")]` attributes of the struct or variant.
+fn parse_template_attr(attrs: &[syn::Attribute]) -> syn::Result<std::option::Option<syn::LitStr>> {
+    let mut template = std::option::Option::None;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("display")) {
+        if template.is_some() {
+            return syn::Result::Err(syn::Error::new_spanned(
+                attr,
+                "duplicate `#[display(\"...\")]` template",
+            ));
+        }
+        template = std::option::Option::Some(attr.parse_args::<syn::LitStr>()?);
+    }
+    syn::Result::Ok(template)
+}
+
+/// Index of a field a template refers to, with the formatting trait printing it if any.
+type TemplateArg = (usize, std::option::Option<&'static str>);
+
+/// Replace the field names or tuple indices in the template with the bindings of the fields,
+/// returning the format string along with the index and formatting trait of every reference.
+/// Fields giving a width or precision, like `w` in `{a:>w$}`, come with no trait.
+fn rewrite_template(
+    template: &syn::LitStr,
+    fields: &syn::Fields,
+) -> syn::Result<(String, Vec<TemplateArg>)> {
+    let value = template.value();
+    let mut format = String::with_capacity(value.len());
+    let mut args = Vec::new();
+    let mut chars = value.chars().peekable();
+    while let std::option::Option::Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == std::option::Option::Some(&'{') => {
+                chars.next();
+                format.push_str("{{");
+            }
+            '}' if chars.peek() == std::option::Option::Some(&'}') => {
+                chars.next();
+                format.push_str("}}");
+            }
+            '}' => {
+                return syn::Result::Err(syn::Error::new_spanned(
+                    template,
+                    "unmatched `}` in template, write `}}` to print a brace",
+                ))
+            }
+            '{' => {
+                let mut name = String::new();
+                let mut spec = String::new();
+                loop {
+                    match chars.next() {
+                        std::option::Option::Some('}') => break,
+                        std::option::Option::Some(c) if !spec.is_empty() || c == ':' => {
+                            spec.push(c)
+                        }
+                        std::option::Option::Some(c) => name.push(c),
+                        std::option::Option::None => {
+                            return syn::Result::Err(syn::Error::new_spanned(
+                                template,
+                                "unmatched `{` in template, write `{{` to print a brace",
+                            ))
+                        }
+                    }
+                }
+                let idx = field_index(template, fields, name.trim())?;
+                let fmt_trait = fmt_trait(&spec);
+                let spec = rewrite_spec(template, fields, &spec, &mut args)?;
+                format.push_str(&format!("{{__self_{}{}}}", idx, spec));
+                args.push((idx, std::option::Option::Some(fmt_trait)));
+            }
+            c => format.push(c),
+        }
+    }
+    syn::Result::Ok((format, args))
+}
+
+/// Replace the fields named by the `name$` widths and precisions of a format spec with their
+/// bindings, adding them to `args`.
+fn rewrite_spec(
+    template: &syn::LitStr,
+    fields: &syn::Fields,
+    spec: &str,
+    args: &mut Vec<TemplateArg>,
+) -> syn::Result<String> {
+    if spec.contains(".*") {
+        return syn::Result::Err(syn::Error::new_spanned(
+            template,
+            "`.*` takes the precision from a positional argument, name a field instead, like `.field$`",
+        ));
+    }
+    let mut rewritten = String::with_capacity(spec.len());
+    // Run of word characters, which names a field when followed by `$`.
+    let mut name = String::new();
+    for c in spec.chars() {
+        match c {
+            c if c.is_alphanumeric() || c == '_' => name.push(c),
+            '$' if !name.is_empty() => {
+                // A `0` opening a width is the zero-padding flag, as in `{a:0w$}` or `{a:#0w$}`,
+                // unless it is the whole name. Precisions have no flags.
+                let field_name =
+                    if name.len() > 1 && name.starts_with('0') && !rewritten.ends_with('.') {
+                        rewritten.push('0');
+                        &name[1..]
+                    } else {
+                        &name[..]
+                    };
+                let idx = field_index(template, fields, field_name)?;
+                rewritten.push_str(&format!("__self_{}$", idx));
+                args.push((idx, std::option::Option::None));
+                name.clear();
+            }
+            c => {
+                rewritten.push_str(&name);
+                rewritten.push(c);
+                name.clear();
+            }
+        }
+    }
+    rewritten.push_str(&name);
+    syn::Result::Ok(rewritten)
+}
+
+/// Resolve a reference in the template to the index of the field it names.
+fn field_index(template: &syn::LitStr, fields: &syn::Fields, name: &str) -> syn::Result<usize> {
+    if name.is_empty() {
+        return syn::Result::Err(syn::Error::new_spanned(
+            template,
+            "fields must be referred to by name, like `{field}`, or by index in tuples, like `{0}`",
+        ));
+    }
+    let idx = match fields {
+        syn::Fields::Named(fields_named) => fields_named
+            .named
+            .iter()
+            .position(|field| field.ident.as_ref().unwrap() == name),
+        syn::Fields::Unnamed(_) => name.parse().ok().filter(|idx| *idx < fields.len()),
+        syn::Fields::Unit => std::option::Option::None,
+    };
+    idx.ok_or_else(|| syn::Error::new_spanned(template, format!("no field `{}` to display", name)))
+}
+
+/// The formatting trait a format spec like `:>8` or `:#x` calls, named after the type it ends in.
+fn fmt_trait(spec: &str) -> &'static str {
+    match spec.chars().last() {
+        std::option::Option::Some('?') => "Debug",
+        std::option::Option::Some('x') => "LowerHex",
+        std::option::Option::Some('X') => "UpperHex",
+        std::option::Option::Some('o') => "Octal",
+        std::option::Option::Some('b') => "Binary",
+        std::option::Option::Some('e') => "LowerExp",
+        std::option::Option::Some('E') => "UpperExp",
+        _ => "Display",
+    }
+}
@@ -1,7 +1,10 @@
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{format_ident, quote};
+use syn::visit::Visit;
 use syn::{parse_macro_input, parse_quote, DeriveInput};
 
+mod display;
+
 #[proc_macro_derive(CustomDebug, attributes(debug))]
 pub fn derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -13,179 +16,808 @@ pub fn derive(input: TokenStream) -> TokenStream {
     .into()
 }
 
+#[proc_macro_derive(CustomDisplay, attributes(display))]
+pub fn derive_display(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match display::expand(&input) {
+        syn::Result::Ok(tt) => tt,
+        syn::Result::Err(err) => err.to_compile_error(),
+    }
+    .into()
+}
+
 fn parse_derive_input(input: &syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
-    println!("{:#?}", &input);
-    let struct_ident = &input.ident;
-    let struct_data = parse_data(&input.data)?;
-    let struct_fields = &struct_data.fields;
-    let generics = add_debug_bound(struct_fields, input.generics.clone());
+    let ident = &input.ident;
+    let type_attrs = parse_type_attrs(&input.attrs)?;
+    let fields = parse_data(&input.data, &type_attrs)?;
+    let generics = match &type_attrs.bound {
+        // Handwritten bounds replace the inferred ones altogether.
+        std::option::Option::Some(bound) => {
+            let mut generics = input.generics.clone();
+            generics
+                .make_where_clause()
+                .predicates
+                .extend(bound.clone());
+            generics
+        }
+        std::option::Option::None => {
+            // Fields with handwritten bounds only replace the bounds inferred from their type,
+            // while skipped and redacted fields aren't printed and need no bounds at all.
+            let mut inferred_fields = Vec::new();
+            let mut field_bounds = Vec::new();
+            for field in &fields {
+                let field_attrs = parse_field_attrs(field)?;
+                match field_attrs.bound {
+                    std::option::Option::Some(bound) => field_bounds.extend(bound),
+                    std::option::Option::None
+                        if field_attrs.skip
+                            || field_attrs.redact.is_some()
+                            || field_attrs.with.is_some() => {}
+                    std::option::Option::None => inferred_fields.push(*field),
+                }
+            }
+            let mut generics = add_fmt_bound(
+                &inferred_fields,
+                input.generics.clone(),
+                &format_ident!("Debug"),
+            );
+            generics.make_where_clause().predicates.extend(field_bounds);
+            generics
+        }
+    };
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    let struct_ident_str = format!("{}", struct_ident);
-    let debug_fields = match struct_fields {
-        syn::Fields::Named(fields_named) => handle_named_fields(fields_named)?,
-        syn::Fields::Unnamed(fields_unnamed) => {
-            let field_indexes = (0..fields_unnamed.unnamed.len()).map(syn::Index::from);
-            let field_indexes_str = (0..fields_unnamed.unnamed.len()).map(|idx| format!("{}", idx));
-            quote!(#( .field(#field_indexes_str, &self.#field_indexes) )*)
-        }
-        syn::Fields::Unit => quote!(),
+    let union_field = type_attrs.union_field.as_ref();
+    if type_attrs.with.is_some() && (type_attrs.transparent || union_field.is_some()) {
+        return syn::Result::Err(syn::Error::new_spanned(
+            ident,
+            "`debug(with = \"...\")` can't be combined with `debug(transparent)` or \
+             `debug(union_field = \"...\")`",
+        ));
+    }
+    let body = match &input.data {
+        syn::Data::Struct(_) | syn::Data::Enum(_) if union_field.is_some() => {
+            return syn::Result::Err(syn::Error::new_spanned(
+                ident,
+                "`debug(union_field = \"...\")` is only supported on unions",
+            ))
+        }
+        _ if type_attrs.with.is_some() => {
+            let with = type_attrs.with.as_ref().unwrap();
+            quote!(#with(self, f))
+        }
+        syn::Data::Struct(data_struct) if type_attrs.transparent => {
+            handle_transparent(ident, data_struct)?
+        }
+        syn::Data::Struct(data_struct) => handle_struct(ident, data_struct)?,
+        syn::Data::Enum(_) if type_attrs.transparent => {
+            return syn::Result::Err(syn::Error::new_spanned(
+                ident,
+                "`debug(transparent)` is only supported on structs",
+            ))
+        }
+        syn::Data::Enum(data_enum) => handle_enum(ident, data_enum)?,
+        syn::Data::Union(_) if type_attrs.transparent => {
+            return syn::Result::Err(syn::Error::new_spanned(
+                ident,
+                "`debug(transparent)` is only supported on structs",
+            ))
+        }
+        syn::Data::Union(data_union) => {
+            // `parse_data` rejects unions with neither a type level `with` nor a field to print.
+            handle_union(ident, data_union, union_field.unwrap())?
+        }
+    };
+    let body = if type_attrs.compact {
+        compact_body(body)
+    } else {
+        body
     };
 
-    syn::Result::Ok(quote!(
-        impl #impl_generics std::fmt::Debug for #struct_ident #ty_generics #where_clause {
+    let mut debug_with = std::option::Option::None;
+    for field in &fields {
+        if parse_field_attrs(field)?.with.is_some() {
+            debug_with = std::option::Option::Some(debug_with_struct());
+            break;
+        }
+    }
+
+    let output = quote!(
+        impl #impl_generics std::fmt::Debug for #ident #ty_generics #where_clause {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                f.debug_struct(#struct_ident_str)
-                #debug_fields
-                .finish()
+                #debug_with
+                #body
             }
         }
-    ))
+    );
+    if type_attrs.trace || std::env::var_os(TRACE_ENV_VAR).is_some() {
+        trace_expansion(ident, &output)?;
+    }
+    syn::Result::Ok(output)
+}
+
+/// Generate `__DebugWith`, printing a value through a `debug(with = "...")` function.
+fn debug_with_struct() -> proc_macro2::TokenStream {
+    quote!(
+        struct __DebugWith<'a, T: ?Sized>(
+            &'a T,
+            fn(&T, &mut std::fmt::Formatter<'_>) -> std::fmt::Result,
+        );
+        impl<T: ?Sized> std::fmt::Debug for __DebugWith<'_, T> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                (self.1)(self.0, f)
+            }
+        }
+    )
 }
 
-fn handle_named_fields(fields: &syn::FieldsNamed) -> syn::Result<proc_macro2::TokenStream> {
-    fields.named.iter().map(parse_named_field).collect()
+/// Environment variable turning on `debug(trace)` for every type deriving `CustomDebug`.
+///
+/// Cargo doesn't know the expansion reads it, so setting it has no effect on a crate that is
+/// already built until something else makes Cargo rebuild the crate, like editing one of its
+/// sources or `cargo clean -p <crate>`.
+const TRACE_ENV_VAR: &str = "DERIVE_DEBUG_TRACE";
+
+/// Write the generated impl to `derive_debug/<crate>/<Type>.rs` under the `OUT_DIR` of the crate
+/// being compiled if it has a build script, or under its target directory otherwise.
+///
+/// Types sharing a name in different modules of one crate share the file, the last one expanded
+/// overwriting the others.
+fn trace_expansion(ident: &syn::Ident, output: &proc_macro2::TokenStream) -> syn::Result<()> {
+    let crate_name =
+        std::env::var("CARGO_CRATE_NAME").unwrap_or_else(|_| "unknown_crate".to_owned());
+    let dir = std::env::var_os("OUT_DIR")
+        .or_else(|| std::env::var_os("CARGO_TARGET_DIR"))
+        .map(std::path::PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("CARGO_MANIFEST_DIR").map(|manifest_dir| {
+                workspace_root(std::path::Path::new(&manifest_dir)).join("target")
+            })
+        })
+        .unwrap_or_else(|| std::path::PathBuf::from("target"))
+        .join("derive_debug")
+        .join(crate_name);
+    let path = dir.join(format!("{}.rs", ident));
+    std::fs::create_dir_all(&dir)
+        .and_then(|()| std::fs::write(&path, output.to_string()))
+        .map_err(|err| {
+            syn::Error::new_spanned(
+                ident,
+                format!(
+                    "failed to write the expansion trace to {}: {}",
+                    path.display(),
+                    err
+                ),
+            )
+        })
 }
 
-fn parse_named_field(field: &syn::Field) -> syn::Result<proc_macro2::TokenStream> {
-    let ident = field.ident.as_ref().unwrap();
-    let ident_str = format!("{}", ident);
-    if field.attrs.is_empty() {
-        syn::Result::Ok(quote!(.field(#ident_str, &self.#ident)))
+/// Find the root of the workspace holding the package at `manifest_dir`, where Cargo puts the
+/// target directory shared by its members: the closest directory with a `[workspace]` manifest,
+/// or the package itself outside of a workspace.
+fn workspace_root(manifest_dir: &std::path::Path) -> &std::path::Path {
+    manifest_dir
+        .ancestors()
+        .find(|dir| {
+            std::fs::read_to_string(dir.join("Cargo.toml"))
+                .is_ok_and(|manifest| manifest.lines().any(|line| line.trim() == "[workspace]"))
+        })
+        .unwrap_or(manifest_dir)
+}
+
+fn handle_struct(
+    struct_ident: &syn::Ident,
+    data_struct: &syn::DataStruct,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let struct_ident_str = format!("{}", struct_ident);
+    let values = data_struct
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(idx, field)| match &field.ident {
+            std::option::Option::Some(ident) => quote!(&self.#ident),
+            std::option::Option::None => {
+                let idx = syn::Index::from(idx);
+                quote!(&self.#idx)
+            }
+        })
+        .collect();
+    debug_fields(&struct_ident_str, &data_struct.fields, values)
+}
+
+/// Print the only field of a newtype, with its field attributes applied, in place of the struct.
+fn handle_transparent(
+    struct_ident: &syn::Ident,
+    data_struct: &syn::DataStruct,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let field = match data_struct.fields.iter().collect::<Vec<_>>()[..] {
+        [field] => field,
+        _ => {
+            return syn::Result::Err(syn::Error::new_spanned(
+                struct_ident,
+                "`debug(transparent)` requires a struct with exactly one field",
+            ))
+        }
+    };
+    let field_attrs = parse_field_attrs(field)?;
+    if field_attrs.skip {
+        return syn::Result::Err(syn::Error::new_spanned(
+            field,
+            "the field of a `debug(transparent)` struct can't be skipped",
+        ));
+    }
+    let value = match &field.ident {
+        std::option::Option::Some(ident) => quote!(&self.#ident),
+        std::option::Option::None => quote!(&self.0),
+    };
+    let value = debug_value(&field_attrs, value);
+    syn::Result::Ok(quote!(std::fmt::Debug::fmt(#value, f)))
+}
+
+/// Run `body` against a formatter without the `{:#?}` flag, so that it prints on a single line.
+fn compact_body(body: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    quote!(
+        struct __DebugCompact<F>(F);
+        impl<F> std::fmt::Debug for __DebugCompact<F>
+        where
+            F: Fn(&mut std::fmt::Formatter<'_>) -> std::fmt::Result,
+        {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                (self.0)(f)
+            }
+        }
+        if f.alternate() {
+            return std::write!(
+                f,
+                "{:?}",
+                __DebugCompact(|f: &mut std::fmt::Formatter<'_>| #body),
+            );
+        }
+        #body
+    )
+}
+
+/// Print the field of the union chosen by `debug(union_field = "...")`, trusting the user that it
+/// always holds a valid value, and mark the output non-exhaustive if the union has other fields.
+fn handle_union(
+    union_ident: &syn::Ident,
+    data_union: &syn::DataUnion,
+    union_field: &syn::Ident,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let union_ident_str = format!("{}", union_ident);
+    let field = data_union
+        .fields
+        .named
+        .iter()
+        .find(|field| field.ident.as_ref() == std::option::Option::Some(union_field))
+        .ok_or_else(|| {
+            syn::Error::new_spanned(
+                union_field,
+                format!("no field `{}` in union `{}`", union_field, union_ident),
+            )
+        })?;
+    let field_attrs = parse_field_attrs(field)?;
+    if field_attrs.skip {
+        return syn::Result::Err(syn::Error::new_spanned(
+            field,
+            "the `union_field` of a union can't be skipped",
+        ));
+    }
+    let name = match &field_attrs.rename {
+        std::option::Option::Some(rename) => rename.value(),
+        std::option::Option::None => format!("{}", union_field),
+    };
+    let value = debug_value(&field_attrs, quote!(unsafe { &self.#union_field }));
+    let finish = if data_union.fields.named.len() > 1 {
+        quote!(finish_non_exhaustive)
     } else {
-        parse_named_field_attrs(field)
+        quote!(finish)
+    };
+    syn::Result::Ok(quote!(
+        f.debug_struct(#union_ident_str).field(#name, #value).#finish()
+    ))
+}
+
+/// Format each variant like `#[derive(Debug)]` does, matching on `self` and binding the fields.
+fn handle_enum(
+    enum_ident: &syn::Ident,
+    data_enum: &syn::DataEnum,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if data_enum.variants.is_empty() {
+        return syn::Result::Ok(quote!(match *self {}));
     }
+    let arms = data_enum
+        .variants
+        .iter()
+        .map(|variant| handle_variant(enum_ident, variant))
+        .collect::<syn::Result<Vec<_>>>()?;
+    syn::Result::Ok(quote!(
+        match self {
+            #(#arms)*
+        }
+    ))
 }
 
-fn parse_named_field_attrs(field: &syn::Field) -> syn::Result<proc_macro2::TokenStream> {
-    let ident = field.ident.as_ref().unwrap();
-    let ident_str = format!("{}", ident);
-    let attr = field.attrs.last().unwrap();
-    if !attr.path.is_ident("debug") {
+fn handle_variant(
+    enum_ident: &syn::Ident,
+    variant: &syn::Variant,
+) -> syn::Result<proc_macro2::TokenStream> {
+    // Variants are printed by name with their fields, there is nothing to configure on them.
+    if let std::option::Option::Some(attr) = variant
+        .attrs
+        .iter()
+        .find(|attr| attr.path.is_ident("debug"))
+    {
         return syn::Result::Err(syn::Error::new_spanned(
-            &attr.path,
-            "value must be \"debug\"",
+            attr,
+            "`debug` attributes are not supported on enum variants, only on their fields",
         ));
     }
-    let attr_meta = &attr.parse_meta();
-    match attr_meta {
-        Ok(syn::Meta::NameValue(syn::MetaNameValue { lit, .. })) => {
-            let debug_assign_value = lit;
-            syn::Result::Ok(quote!(
-                .field(#ident_str, &std::format_args!(#debug_assign_value, &self.#ident))
-            ))
+    let variant_ident = &variant.ident;
+    let variant_ident_str = format!("{}", variant_ident);
+    let bindings = field_bindings(&variant.fields);
+    let values = bindings.iter().map(|binding| quote!(#binding)).collect();
+    let debug_fields = debug_fields(&variant_ident_str, &variant.fields, values)?;
+    let pattern = fields_pattern(
+        quote!(#enum_ident::#variant_ident),
+        &variant.fields,
+        &bindings,
+    );
+    syn::Result::Ok(quote!(#pattern => #debug_fields,))
+}
+
+/// Name the fields `__self_<idx>` when binding them, as their names could shadow the formatter.
+fn field_bindings(fields: &syn::Fields) -> Vec<syn::Ident> {
+    (0..fields.len())
+        .map(|idx| format_ident!("__self_{}", idx))
+        .collect()
+}
+
+/// Build the pattern matching the struct or variant at `path` and binding its fields.
+fn fields_pattern(
+    path: proc_macro2::TokenStream,
+    fields: &syn::Fields,
+    bindings: &[syn::Ident],
+) -> proc_macro2::TokenStream {
+    match fields {
+        syn::Fields::Named(fields_named) => {
+            let field_idents = fields_named
+                .named
+                .iter()
+                .map(|field| field.ident.as_ref().unwrap());
+            quote!(#path { #(#field_idents: #bindings),* })
         }
-        Ok(meta) => syn::Result::Err(syn::Error::new_spanned(meta, "expected meta name value")),
-        Err(err) => syn::Result::Err(err.clone()),
+        syn::Fields::Unnamed(_) => quote!(#path(#(#bindings),*)),
+        syn::Fields::Unit => quote!(#path),
     }
 }
 
-fn parse_data(data: &syn::Data) -> syn::Result<&syn::DataStruct> {
-    match data {
-        syn::Data::Struct(data_struct) => syn::Result::Ok(data_struct),
-        syn::Data::Enum(syn::DataEnum { enum_token, .. }) => syn::Result::Err(
-            syn::Error::new_spanned(enum_token, "CustomDebug is not implemented for enums"),
-        ),
-        syn::Data::Union(syn::DataUnion { union_token, .. }) => syn::Result::Err(
-            syn::Error::new_spanned(union_token, "CustomDebug is not implemented for unions"),
-        ),
+/// Print `fields` the way `#[derive(Debug)]` would, given references to their values.
+///
+/// Skipped fields are left out, and mark the output as non-exhaustive.
+fn debug_fields(
+    name: &str,
+    fields: &syn::Fields,
+    values: Vec<proc_macro2::TokenStream>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut debug_fields = Vec::new();
+    let mut finish = quote!(finish);
+    for (field, value) in fields.iter().zip(values) {
+        let field_attrs = parse_field_attrs(field)?;
+        if field_attrs.skip {
+            finish = quote!(finish_non_exhaustive);
+            continue;
+        }
+        let value = debug_value(&field_attrs, value);
+        debug_fields.push(match (&field.ident, field_attrs.rename) {
+            (std::option::Option::Some(_), std::option::Option::Some(rename)) => {
+                quote!(.field(#rename, #value))
+            }
+            (std::option::Option::Some(ident), std::option::Option::None) => {
+                let ident_str = format!("{}", ident);
+                quote!(.field(#ident_str, #value))
+            }
+            (std::option::Option::None, std::option::Option::Some(rename)) => {
+                return syn::Result::Err(syn::Error::new_spanned(
+                    rename,
+                    "`rename` is only supported on named fields",
+                ));
+            }
+            (std::option::Option::None, std::option::Option::None) => quote!(.field(#value)),
+        });
     }
+    syn::Result::Ok(match fields {
+        syn::Fields::Named(_) => quote!(f.debug_struct(#name) #(#debug_fields)* .#finish()),
+        syn::Fields::Unnamed(_) => quote!(f.debug_tuple(#name) #(#debug_fields)* .#finish()),
+        syn::Fields::Unit => quote!(f.write_str(#name)),
+    })
 }
 
-fn add_debug_bound(fields: &syn::Fields, mut generics: syn::Generics) -> syn::Generics {
-    let mut phantom_ty_idents = std::collections::HashSet::new();
-    let mut non_phantom_ty_idents = std::collections::HashSet::new();
-    let g = generics.clone();
-    for (ident, opt_iter) in fields
-        .iter()
-        .flat_map(extract_ty_path)
-        .map(|path| extract_ty_idents(path, g.params.iter().flat_map(|p| {
-            if let syn::GenericParam::Type(ty) = p {
-                std::option::Option::Some(&ty.ident)
-            } else {
-                std::option::Option::None
+/// Build the `&dyn Debug` expression printing a field, given a reference to its value.
+fn debug_value(
+    field_attrs: &FieldAttrs,
+    value: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    if let std::option::Option::Some(with) = &field_attrs.with {
+        return quote!(&__DebugWith(#value, #with));
+    }
+    match (&field_attrs.redact, &field_attrs.format) {
+        (std::option::Option::Some(Redact::Value), _) => {
+            quote!(&std::format_args!("<redacted>"))
+        }
+        (std::option::Option::Some(Redact::Len), _) => {
+            quote!(&std::format_args!("<redacted, len {}>", (#value).len()))
+        }
+        (std::option::Option::None, std::option::Option::Some(format)) => {
+            quote!(&std::format_args!(#format, #value))
+        }
+        (std::option::Option::None, std::option::Option::None) => value,
+    }
+}
+
+/// Options from the `#[debug(...)]` attributes of the derived type.
+#[derive(Default)]
+struct TypeAttrs {
+    /// Where predicates replacing all of the inferred bounds, from `bound = "..."`.
+    bound: std::option::Option<Bound>,
+    /// Write the generated impl to a file, from `trace`.
+    trace: bool,
+    /// Print on a single line even with `{:#?}`, from `compact`.
+    compact: bool,
+    /// Print the only field of the struct in place of the struct, from `transparent`.
+    transparent: bool,
+    /// Field of the union to print, from `union_field = "..."`.
+    ///
+    /// Reading it is unsafe: by choosing the field, the user vouches that every value of the union
+    /// has a valid value in it, like an integer field spanning the whole union. A field with
+    /// invalid bit patterns, like a `bool`, makes printing undefined behaviour.
+    union_field: std::option::Option<syn::Ident>,
+    /// Function printing the whole value in place of the generated code, from `with = "..."`.
+    with: std::option::Option<syn::Path>,
+}
+
+/// Options from the `#[debug = "..."]` and `#[debug(...)]` attributes of a field.
+#[derive(Default)]
+struct FieldAttrs {
+    /// Format string applied to the field, from `#[debug = "..."]`.
+    format: std::option::Option<syn::LitStr>,
+    /// Where predicates replacing the bounds inferred from the field, from `bound = "..."`.
+    bound: std::option::Option<Bound>,
+    /// Leave the field out of the output, from `skip`.
+    skip: bool,
+    /// Hide the value of the field, from `redact` or `redact = "len"`.
+    redact: std::option::Option<Redact>,
+    /// Function printing the field in place of its `Debug` impl, from `with = "..."`.
+    with: std::option::Option<syn::Path>,
+    /// Name printed for the field instead of its identifier, from `rename = "..."`.
+    rename: std::option::Option<syn::LitStr>,
+}
+
+/// How much of a redacted field is printed.
+enum Redact {
+    /// Nothing but `<redacted>`.
+    Value,
+    /// The length of the value, as returned by its `len` method.
+    Len,
+}
+
+type Bound = syn::punctuated::Punctuated<syn::WherePredicate, syn::Token![,]>;
+
+fn parse_type_attrs(attrs: &[syn::Attribute]) -> syn::Result<TypeAttrs> {
+    let mut type_attrs = TypeAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("debug")) {
+        for meta in parse_debug_list(attr)? {
+            match &meta {
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(lit_str),
+                    ..
+                }) if path.is_ident("bound") => {
+                    // Repeated bounds add up rather than replace each other.
+                    type_attrs
+                        .bound
+                        .get_or_insert_with(Bound::new)
+                        .extend(parse_bound(lit_str)?);
+                }
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(lit_str),
+                    ..
+                }) if path.is_ident("union_field") => {
+                    type_attrs.union_field = std::option::Option::Some(lit_str.parse()?);
+                }
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(lit_str),
+                    ..
+                }) if path.is_ident("with") => {
+                    type_attrs.with = std::option::Option::Some(lit_str.parse()?);
+                }
+                syn::Meta::Path(path) if path.is_ident("trace") => type_attrs.trace = true,
+                syn::Meta::Path(path) if path.is_ident("compact") => type_attrs.compact = true,
+                syn::Meta::Path(path) if path.is_ident("transparent") => {
+                    type_attrs.transparent = true;
+                }
+                _ => {
+                    return syn::Result::Err(syn::Error::new_spanned(
+                        meta,
+                        "expected one of `debug(bound = \"...\")`, `debug(with = \"...\")`, \
+                         `debug(union_field = \"...\")`, `debug(trace)`, \
+                         `debug(compact)` or `debug(transparent)`",
+                    ))
+                }
             }
-        } ).collect()))
+        }
+    }
+    syn::Result::Ok(type_attrs)
+}
+
+fn parse_field_attrs(field: &syn::Field) -> syn::Result<FieldAttrs> {
+    let mut field_attrs = FieldAttrs::default();
+    // The options given, by name, to report the ones contradicting each other.
+    let mut options: Vec<(&str, proc_macro2::TokenStream)> = Vec::new();
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("debug"))
     {
-        if ident == "PhantomData" {
-            // If the field type ident is `PhantomData`
-            // add the generic parameters into the phantom idents collection
-            if let std::option::Option::Some(args) = opt_iter {
-                for arg in args {
-                    phantom_ty_idents.insert(arg);
+        if let syn::Meta::NameValue(syn::MetaNameValue { lit, .. }) = attr.parse_meta()? {
+            match lit {
+                syn::Lit::Str(lit_str) => {
+                    options.push(("format", quote!(#lit_str)));
+                    field_attrs.format = std::option::Option::Some(lit_str);
+                }
+                lit => {
+                    return syn::Result::Err(syn::Error::new_spanned(
+                        lit,
+                        "expected a format string",
+                    ))
                 }
             }
-        } else {
-            // Else, add the type and existing generic parameters into the non-phantom idents collection
-            non_phantom_ty_idents.insert(ident);
-            if let std::option::Option::Some(args) = opt_iter {
-                for arg in args {
-                    non_phantom_ty_idents.insert(arg);
+            continue;
+        }
+        for meta in parse_debug_list(attr)? {
+            if let std::option::Option::Some(name) = ["bound", "with", "rename", "skip", "redact"]
+                .iter()
+                .find(|name| meta.path().is_ident(name))
+            {
+                let path = meta.path();
+                options.push((name, quote!(#path)));
+            }
+            match &meta {
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(lit_str),
+                    ..
+                }) if path.is_ident("bound") => {
+                    // Repeated bounds add up rather than replace each other.
+                    field_attrs
+                        .bound
+                        .get_or_insert_with(Bound::new)
+                        .extend(parse_bound(lit_str)?);
+                }
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(lit_str),
+                    ..
+                }) if path.is_ident("with") => {
+                    field_attrs.with = std::option::Option::Some(lit_str.parse()?);
+                }
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(lit_str),
+                    ..
+                }) if path.is_ident("rename") => {
+                    field_attrs.rename = std::option::Option::Some(lit_str.clone());
+                }
+                syn::Meta::Path(path) if path.is_ident("skip") => field_attrs.skip = true,
+                syn::Meta::Path(path) if path.is_ident("redact") => {
+                    field_attrs.redact = std::option::Option::Some(Redact::Value);
+                }
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(lit_str),
+                    ..
+                }) if path.is_ident("redact") && lit_str.value() == "len" => {
+                    field_attrs.redact = std::option::Option::Some(Redact::Len);
+                }
+                _ => {
+                    return syn::Result::Err(syn::Error::new_spanned(
+                        meta,
+                        "expected one of `debug = \"...\"`, `debug(bound = \"...\")`, \
+                         `debug(with = \"...\")`, `debug(rename = \"...\")`, `debug(skip)`, `debug(redact)` or `debug(redact = \"len\")`",
+                    ))
                 }
             }
         }
     }
-    // Find the difference between the phantom idents and non-phantom idents
-    // Collect them into an hash set for O(1) lookup
-    let non_debug_fields = phantom_ty_idents
-        .difference(&non_phantom_ty_idents)
-        .collect::<std::collections::HashSet<_>>();
-    // Iterate generic params and if their ident is NOT in the phantom fields
-    // do not add the generic bound
-    for param in generics.type_params_mut() {
-        // this is kinda shady, hoping it works
-        if !non_debug_fields.contains(&&param.ident) {
-            param.bounds.push(parse_quote!(std::fmt::Debug));
+    let option = |name| {
+        options
+            .iter()
+            .find(|(option_name, _)| *option_name == name)
+            .map(|(_, tokens)| tokens)
+    };
+    if option("skip").is_some() {
+        if let std::option::Option::Some((_, tokens)) = options
+            .iter()
+            .find(|(option_name, _)| *option_name != "skip")
+        {
+            return syn::Result::Err(syn::Error::new_spanned(
+                tokens,
+                "skipped fields are not printed, `skip` cannot be combined with other options",
+            ));
         }
     }
+    if let (std::option::Option::Some(with), std::option::Option::Some(_)) = (
+        option("with"),
+        option("redact").or_else(|| option("format")),
+    ) {
+        return syn::Result::Err(syn::Error::new_spanned(
+            with,
+            "`with` prints the field by itself, it cannot be combined with `redact` or `debug = \"...\"`",
+        ));
+    }
+    if let (std::option::Option::Some(_), std::option::Option::Some(format)) =
+        (option("redact"), option("format"))
+    {
+        return syn::Result::Err(syn::Error::new_spanned(
+            format,
+            "redacted fields hide their value, `debug = \"...\"` cannot be combined with `redact`",
+        ));
+    }
+    syn::Result::Ok(field_attrs)
+}
+
+/// Parse the options of a `#[debug(...)]` attribute.
+fn parse_debug_list(attr: &syn::Attribute) -> syn::Result<Vec<syn::Meta>> {
+    match attr.parse_meta()? {
+        syn::Meta::List(list) => list
+            .nested
+            .into_iter()
+            .map(|nested| match nested {
+                syn::NestedMeta::Meta(meta) => syn::Result::Ok(meta),
+                syn::NestedMeta::Lit(lit) => syn::Result::Err(syn::Error::new_spanned(
+                    lit,
+                    "expected an option of `debug(...)`",
+                )),
+            })
+            .collect(),
+        meta => syn::Result::Err(syn::Error::new_spanned(meta, "expected `debug(...)`")),
+    }
+}
+
+fn parse_bound(lit_str: &syn::LitStr) -> syn::Result<Bound> {
+    lit_str.parse_with(syn::punctuated::Punctuated::parse_terminated)
+}
+
+/// Collect the fields printed from the struct, every variant of the enum, or the union.
+///
+/// A type level `with` function prints the value by itself, so no field is.
+fn parse_data<'a>(data: &'a syn::Data, type_attrs: &TypeAttrs) -> syn::Result<Vec<&'a syn::Field>> {
+    if type_attrs.with.is_some() {
+        return syn::Result::Ok(Vec::new());
+    }
+    match data {
+        syn::Data::Struct(data_struct) => syn::Result::Ok(data_struct.fields.iter().collect()),
+        syn::Data::Enum(data_enum) => syn::Result::Ok(
+            data_enum
+                .variants
+                .iter()
+                .flat_map(|variant| &variant.fields)
+                .collect(),
+        ),
+        syn::Data::Union(syn::DataUnion {
+            union_token,
+            fields,
+        }) => match &type_attrs.union_field {
+            std::option::Option::Some(union_field) => syn::Result::Ok(
+                fields
+                    .named
+                    .iter()
+                    .filter(|field| field.ident.as_ref() == std::option::Option::Some(union_field))
+                    .collect(),
+            ),
+            std::option::Option::None => syn::Result::Err(syn::Error::new_spanned(
+                union_token,
+                "CustomDebug is only implemented for unions with \
+                 `debug(union_field = \"...\")` or `debug(with = \"...\")`",
+            )),
+        },
+    }
+}
+
+/// Add a where predicate on `std::fmt::<fmt_trait>` for every generic parameter, or associated
+/// type of one, which the fields need printed.
+fn add_fmt_bound(
+    fields: &[&syn::Field],
+    mut generics: syn::Generics,
+    fmt_trait: &syn::Ident,
+) -> syn::Generics {
+    let mut collector = BoundCollector::new(
+        generics
+            .type_params()
+            .map(|param| param.ident.clone())
+            .collect(),
+    );
+    for field in fields {
+        collector.visit_type(&field.ty);
+    }
+    let predicates = collector
+        .bounded_tys
+        .iter()
+        .map(|ty| -> syn::WherePredicate { parse_quote!(#ty: std::fmt::#fmt_trait) });
+    generics.make_where_clause().predicates.extend(predicates);
     generics
 }
 
-/// Extract the path from the type path in a field.
-fn extract_ty_path(field: &syn::Field) -> std::option::Option<&syn::Path> {
-    if let syn::Type::Path(syn::TypePath { path, .. }) = &field.ty {
-        std::option::Option::Some(&path)
-    } else {
-        std::option::Option::None
-    }
-}
-
-/// From a `syn::Path` extract both the type ident and an iterator over generic type arguments.
-fn extract_ty_idents<'a>(
-    path: &'a syn::Path,
-    params: std::collections::HashSet<&'a syn::Ident>,
-) -> (
-    &'a syn::Ident,
-    std::option::Option<impl Iterator<Item = &'a syn::Ident>>,
-) {
-    let ty_segment = path.segments.last().unwrap();
-    let ty_ident = &ty_segment.ident;
-    if let syn::PathArguments::AngleBracketed(syn::AngleBracketedGenericArguments {
-        args, ..
-    }) = &ty_segment.arguments
-    {
-        let ident_iter = args.iter().flat_map(move |gen_arg| {
-            if let syn::GenericArgument::Type(syn::Type::Path(syn::TypePath { path, .. })) = gen_arg
-            {
-                match path.segments.len() {
-                    2 => {
-                        let ty = path.segments.first().unwrap();
-                        let assoc_ty = path.segments.last().unwrap();
-                        if params.contains(&ty.ident) {
-                            std::option::Option::Some(&assoc_ty.ident)
-                        } else {
-                            std::option::Option::None
-                        }
-                    }
-                    1 => path.get_ident(),
-                    _ => std::unimplemented!("kinda tired of edge cases"),
-                }
-            } else {
-                std::option::Option::None
+/// Walks the field types for the types whose formatting impls depend on a generic parameter.
+///
+/// Those are the parameters themselves, and their associated types like `T::Value` or
+/// `<T as Trait>::Value`, wherever they are nested. Bounding these rather than whole field types
+/// avoids the recursion and private type issues of bounds like `Option<Box<Two<T>>>: Debug`.
+struct BoundCollector {
+    params: std::collections::HashSet<syn::Ident>,
+    /// Tokens of the types in `bounded_tys`, to bound each of them once.
+    bounded: std::collections::HashSet<String>,
+    bounded_tys: Vec<syn::Type>,
+}
+
+impl BoundCollector {
+    fn new(params: std::collections::HashSet<syn::Ident>) -> Self {
+        BoundCollector {
+            params,
+            bounded: std::collections::HashSet::new(),
+            bounded_tys: Vec::new(),
+        }
+    }
+
+    /// Whether the type depends on any of the generic parameters.
+    fn is_generic(&self, ty: &syn::Type) -> bool {
+        let mut collector = BoundCollector::new(self.params.clone());
+        collector.visit_type(ty);
+        !collector.bounded_tys.is_empty()
+    }
+
+    fn bound(&mut self, ty: syn::Type) {
+        if self.bounded.insert(quote!(#ty).to_string()) {
+            self.bounded_tys.push(ty);
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for BoundCollector {
+    fn visit_type_path(&mut self, type_path: &'ast syn::TypePath) {
+        let is_param_path = match &type_path.qself {
+            // `T` or `T::Value`
+            std::option::Option::None => {
+                type_path.path.leading_colon.is_none()
+                    && type_path.path.segments.first().is_some_and(|segment| {
+                        self.params.contains(&segment.ident) && segment.arguments.is_empty()
+                    })
             }
-        });
-        (ty_ident, std::option::Option::Some(ident_iter))
-    } else {
-        (ty_ident, std::option::Option::None)
+            // `<T as Trait>::Value`
+            std::option::Option::Some(qself) => self.is_generic(&qself.ty),
+        };
+        if is_param_path {
+            self.bound(syn::Type::Path(type_path.clone()));
+            return;
+        }
+        if type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "PhantomData")
+        {
+            return;
+        }
+        syn::visit::visit_type_path(self, type_path);
     }
+
+    // Function pointers implement `Debug` whatever their argument and return types.
+    fn visit_type_bare_fn(&mut self, _: &'ast syn::TypeBareFn) {}
+
+    // Trait objects and opaque types can't be bounded through their generic arguments.
+    fn visit_type_trait_object(&mut self, _: &'ast syn::TypeTraitObject) {}
+
+    fn visit_type_impl_trait(&mut self, _: &'ast syn::TypeImplTrait) {}
 }
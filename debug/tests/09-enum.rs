@@ -0,0 +1,45 @@
+// Enums print like they would with the standard library's #[derive(Debug)]:
+// unit variants as their name, tuple variants through debug_tuple and struct
+// variants through debug_struct. The #[debug = "..."] attribute works on the
+// fields of any variant.
+//
+//     impl Debug for Event {
+//         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+//             match self {
+//                 Event::Start => f.write_str("Start"),
+//                 Event::Key(__self_0) => f.debug_tuple("Key").field(__self_0).finish(),
+//                 ...
+//             }
+//         }
+//     }
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub enum Event<T> {
+    Start,
+    Key(char, #[debug = "0x{:02x}"] u8),
+    Click { x: i32, y: i32, data: T },
+    Stop {},
+}
+
+#[derive(CustomDebug)]
+pub enum Never {}
+
+fn main() {
+    let events = vec![
+        Event::Start,
+        Event::Key('a', 10),
+        Event::Click { x: 1, y: -2, data: "left" },
+        Event::Stop {},
+    ];
+
+    assert_eq!(
+        format!("{:?}", events),
+        r#"[Start, Key('a', 0x0a), Click { x: 1, y: -2, data: "left" }, Stop]"#,
+    );
+    assert_eq!(
+        format!("{:#?}", Event::Key::<()>('b', 1)),
+        "Key(\n    'b',\n    0x01,\n)",
+    );
+}
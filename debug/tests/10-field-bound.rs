@@ -0,0 +1,70 @@
+// A `debug(bound = "...")` attribute on a field substitutes only the bounds
+// inferred from that field's type, keeping the ones inferred from the other
+// fields:
+//
+//     impl<T: Trait, U> Debug for Wrapper<T, U>
+//     where
+//         U: Debug,
+//         T::Value: Debug,
+//     {...}
+//
+// Bounds given in several attributes, on the type or on a field, add up.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+pub trait Trait {
+    type Value;
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "T::Value: Debug")]
+pub struct Field<T: Trait> {
+    values: Vec<T::Value>,
+}
+
+#[derive(CustomDebug)]
+pub struct Wrapper<T: Trait, U> {
+    #[debug(bound = "T::Value: Debug")]
+    field: Field<T>,
+    normal: U,
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "T: Debug")]
+#[debug(bound = "U: Debug")]
+pub struct Pair<T, U> {
+    first: T,
+    second: U,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct Id;
+
+    impl Trait for Id {
+        type Value = u8;
+    }
+
+    assert_debug::<Wrapper<Id, bool>>();
+    assert_eq!(
+        format!(
+            "{:?}",
+            Pair {
+                first: 1,
+                second: 'b'
+            }
+        ),
+        "Pair { first: 1, second: 'b' }"
+    );
+
+    let wrapper = Wrapper::<Id, _> {
+        field: Field { values: vec![1, 2] },
+        normal: "normal",
+    };
+    assert_eq!(
+        format!("{:?}", wrapper),
+        r#"Wrapper { field: Field { values: [1, 2] }, normal: "normal" }"#,
+    );
+}
@@ -0,0 +1,44 @@
+// Bounds are inferred from every generic parameter or associated type found
+// anywhere in a field type: behind references, inside tuples, arrays and
+// slices, and nested in the arguments of other types. Parameters which only
+// show up in PhantomData or in function pointers are left unbounded.
+//
+//     impl<'a, T: Trait, U, V, W> Debug for Nested<'a, T, U, V, W>
+//     where
+//         T::Value: Debug,
+//         <T as Trait>::Value: Debug,
+//         U: Debug,
+//         V: Debug,
+//     {...}
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+pub trait Trait {
+    type Value;
+}
+
+#[derive(CustomDebug)]
+pub struct Nested<'a, T: Trait, U, V, W> {
+    boxed: Box<Vec<T::Value>>,
+    qualified: Option<<T as Trait>::Value>,
+    pair: (u8, &'a U),
+    array: [Option<V>; 2],
+    callback: fn(W) -> W,
+    marker: PhantomData<(T, W)>,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    // Neither implements Debug.
+    struct Id;
+    struct Opaque;
+
+    impl Trait for Id {
+        type Value = u8;
+    }
+
+    assert_debug::<Nested<'static, Id, bool, char, Opaque>>();
+}
@@ -0,0 +1,34 @@
+// Setting #[debug(trace)] on a type, or the DERIVE_DEBUG_TRACE environment
+// variable for every type, writes the generated impl to
+// derive_debug/<crate>/<Type>.rs under the OUT_DIR of the crate if it has a
+// build script, or else under its target directory, to inspect the expansion
+// without cluttering the build output.
+//
+// Cargo doesn't track DERIVE_DEBUG_TRACE, so setting it only affects crates
+// which get rebuilt for another reason.
+
+use derive_debug::CustomDebug;
+use std::path::Path;
+
+#[derive(CustomDebug)]
+#[debug(trace)]
+pub struct Traced {
+    id: u32,
+}
+
+fn main() {
+    let target_dir = match option_env!("CARGO_TARGET_DIR") {
+        Some(target_dir) => Path::new(target_dir).to_owned(),
+        None => Path::new(env!("CARGO_MANIFEST_DIR")).join("target"),
+    };
+    let trace = std::fs::read_to_string(
+        target_dir
+            .join("derive_debug")
+            .join(env!("CARGO_CRATE_NAME"))
+            .join("Traced.rs"),
+    )
+    .unwrap();
+    assert!(trace.contains("impl std :: fmt :: Debug for Traced"));
+
+    assert_eq!(format!("{:?}", Traced { id: 7 }), "Traced { id: 7 }");
+}
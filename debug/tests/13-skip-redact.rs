@@ -0,0 +1,55 @@
+// A field marked #[debug(skip)] is left out of the output, which then ends
+// with `..` as printed by finish_non_exhaustive(). A field marked
+// #[debug(redact)] prints as <redacted>, or with #[debug(redact = "len")] as
+// <redacted, len N> using the `len` method of its value. Neither kind of field
+// needs to implement Debug.
+
+use derive_debug::CustomDebug;
+
+pub struct Connection;
+
+#[derive(CustomDebug)]
+pub struct Login<C> {
+    user: &'static str,
+    #[debug(redact)]
+    password: String,
+    #[debug(redact = "len")]
+    token: Vec<u8>,
+    #[debug(skip)]
+    connection: C,
+}
+
+#[derive(CustomDebug)]
+pub enum Credentials {
+    Token(#[debug(redact = "len")] String, #[debug(skip)] Connection),
+    Password {
+        user: &'static str,
+        #[debug(redact)]
+        password: String,
+    },
+}
+
+fn main() {
+    let login = Login {
+        user: "root",
+        password: "hunter2".to_owned(),
+        token: vec![0; 16],
+        connection: Connection,
+    };
+    assert_eq!(
+        format!("{:?}", login),
+        r#"Login { user: "root", password: <redacted>, token: <redacted, len 16>, .. }"#,
+    );
+
+    let token = Credentials::Token("secret".to_owned(), Connection);
+    assert_eq!(format!("{:?}", token), "Token(<redacted, len 6>, ..)");
+
+    let password = Credentials::Password {
+        user: "root",
+        password: "hunter2".to_owned(),
+    };
+    assert_eq!(
+        format!("{:?}", password),
+        r#"Password { user: "root", password: <redacted> }"#,
+    );
+}
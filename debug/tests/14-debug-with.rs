@@ -0,0 +1,58 @@
+// A field marked #[debug(with = "path::to::fmt_fn")] is printed by calling
+// the given function, with signature fn(&T, &mut fmt::Formatter) ->
+// fmt::Result, instead of the field's own Debug impl, which it then doesn't
+// need to have.
+
+use derive_debug::CustomDebug;
+use std::fmt;
+
+mod fmt_fns {
+    use std::fmt;
+
+    pub fn hex(bytes: &Vec<u8>, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in bytes {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Timestamp(u64);
+
+fn seconds(timestamp: &Timestamp, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}s", timestamp.0)
+}
+
+fn count<T>(items: &Vec<T>, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} items", items.len())
+}
+
+#[derive(CustomDebug)]
+pub struct Packet<T> {
+    #[debug(with = "fmt_fns::hex")]
+    payload: Vec<u8>,
+    #[debug(with = "seconds")]
+    sent: Timestamp,
+    #[debug(with = "count")]
+    attachments: Vec<T>,
+}
+
+#[derive(CustomDebug)]
+pub enum Event {
+    Received(#[debug(with = "fmt_fns::hex")] Vec<u8>),
+}
+
+fn main() {
+    let packet = Packet {
+        payload: vec![0xde, 0xad, 0xbe, 0xef],
+        sent: Timestamp(42),
+        attachments: vec![Timestamp(0)],
+    };
+    assert_eq!(
+        format!("{:?}", packet),
+        "Packet { payload: deadbeef, sent: 42s, attachments: 1 items }",
+    );
+
+    let event = Event::Received(vec![1, 2]);
+    assert_eq!(format!("{:?}", event), "Received(0102)");
+}
@@ -0,0 +1,34 @@
+// Tuple structs print like with the standard library's #[derive(Debug)],
+// through debug_tuple, and the field attributes work on their positional
+// fields too. The name printed for a named field can be changed with
+// #[debug(rename = "...")].
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Rgb(#[debug = "0x{:02x}"] u8, u8, #[debug(skip)] u8);
+
+#[derive(CustomDebug)]
+pub struct Unit;
+
+#[derive(CustomDebug)]
+pub struct Request {
+    #[debug(rename = "type")]
+    kind: &'static str,
+    #[debug(rename = "auth", redact)]
+    authorization: String,
+}
+
+fn main() {
+    assert_eq!(format!("{:?}", Rgb(255, 128, 0)), "Rgb(0xff, 128, ..)");
+    assert_eq!(format!("{:?}", Unit), "Unit");
+
+    let request = Request {
+        kind: "GET",
+        authorization: "Bearer abc".to_owned(),
+    };
+    assert_eq!(
+        format!("{:?}", request),
+        r#"Request { type: "GET", auth: <redacted> }"#,
+    );
+}
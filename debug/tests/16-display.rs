@@ -0,0 +1,73 @@
+// The crate also derives Display through #[derive(CustomDisplay)], laying
+// out the struct, or each enum variant, from a #[display("...")] template.
+// Fields are referred to inline by name, or by index in tuple structs and
+// variants, and accept the usual format specs, whose widths and precisions
+// can come from fields too, like {name:>width$}. Only the type parameters of
+// the fields a template prints are bounded, by the trait its spec calls.
+
+use derive_debug::CustomDisplay;
+use std::marker::PhantomData;
+
+#[derive(CustomDisplay)]
+#[display("{from} -> {to}")]
+pub struct Edge<T> {
+    from: T,
+    to: T,
+}
+
+#[derive(CustomDisplay)]
+#[display("#{0:06x}")]
+pub struct Color(u32);
+
+#[derive(CustomDisplay)]
+pub enum Event<K, M> {
+    #[display("key {key:?} pressed {{{count}x}}")]
+    Press {
+        key: K,
+        count: usize,
+    },
+    #[display("moved to ({0}, {1})")]
+    Move(i32, i32),
+    Idle,
+    #[display("tagged")]
+    Tagged(PhantomData<M>),
+}
+
+#[derive(CustomDisplay)]
+#[display("[{label:>width$}] {ratio:.digits$} #{id:0width$} {id:#0width$x}")]
+pub struct Gauge {
+    label: &'static str,
+    width: usize,
+    ratio: f64,
+    digits: usize,
+    id: u8,
+}
+
+struct NotDisplay;
+
+fn main() {
+    let edge = Edge { from: 'a', to: 'b' };
+    assert_eq!(edge.to_string(), "a -> b");
+    assert_eq!(Color(0xff8000).to_string(), "#ff8000");
+
+    let gauge = Gauge {
+        label: "cpu",
+        width: 5,
+        ratio: 0.25,
+        digits: 1,
+        id: 42,
+    };
+    assert_eq!(gauge.to_string(), "[  cpu] 0.2 #00042 0x02a");
+
+    let press = Event::<_, NotDisplay>::Press { key: "q", count: 2 };
+    assert_eq!(press.to_string(), r#"key "q" pressed {2x}"#);
+    assert_eq!(
+        Event::<&str, NotDisplay>::Move(1, -2).to_string(),
+        "moved to (1, -2)"
+    );
+    assert_eq!(Event::<&str, NotDisplay>::Idle.to_string(), "Idle");
+    assert_eq!(
+        Event::<&str, NotDisplay>::Tagged(PhantomData).to_string(),
+        "tagged",
+    );
+}
@@ -0,0 +1,54 @@
+// A type marked #[debug(compact)] stays on a single line even when printed
+// with {:#?}, fields included, which suits small values nested in larger
+// pretty-printed structures.
+//
+// A newtype marked #[debug(transparent)] prints its only field in place of
+// itself, the way wrapper types like `UserId(u64)` are usually shown, with
+// the attributes of the field still applied.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(compact)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct UserId(u64);
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Token {
+    #[debug(redact = "len")]
+    secret: String,
+}
+
+#[derive(CustomDebug)]
+pub struct Shape {
+    id: UserId,
+    origin: Point,
+}
+
+fn main() {
+    let point = Point { x: 1, y: 2 };
+    assert_eq!(format!("{:?}", point), "Point { x: 1, y: 2 }");
+    assert_eq!(format!("{:#?}", point), "Point { x: 1, y: 2 }");
+
+    assert_eq!(format!("{:?}", UserId(5)), "5");
+    let token = Token {
+        secret: "hunter2".to_owned(),
+    };
+    assert_eq!(format!("{:?}", token), "<redacted, len 7>");
+
+    let shape = Shape {
+        id: UserId(5),
+        origin: point,
+    };
+    assert_eq!(
+        format!("{:#?}", shape),
+        "Shape {\n    id: 5,\n    origin: Point { x: 1, y: 2 },\n}",
+    );
+}
@@ -0,0 +1,46 @@
+// Unions can't be printed without knowing which of their fields holds a
+// value, so CustomDebug only accepts them given either the field to print,
+// with #[debug(union_field = "...")], or a function printing the whole
+// value, with #[debug(with = "...")] on the type.
+//
+// Safety: reading a union field is unsafe, and union_field is a promise from
+// the user that the generated impl can read the chosen field whatever was
+// written to the union. It must hold a valid value for every bit pattern the
+// union can take, like the integer field covering every byte that FFI unions
+// usually have. A `bool` field, for one, would be undefined behaviour to
+// print. The output is marked non-exhaustive since the other fields are left
+// out.
+
+use derive_debug::CustomDebug;
+use std::fmt;
+
+#[derive(CustomDebug)]
+#[debug(union_field = "bits")]
+#[repr(C)]
+pub union Value {
+    #[debug = "0x{:08x}"]
+    bits: u32,
+    float: f32,
+}
+
+#[derive(CustomDebug)]
+#[debug(with = "fmt_tagged")]
+#[repr(C)]
+pub union Tagged {
+    int: i32,
+    uint: u32,
+}
+
+fn fmt_tagged(tagged: &Tagged, f: &mut fmt::Formatter) -> fmt::Result {
+    // SAFETY: both fields are plain integers of the same size.
+    let int = unsafe { tagged.int };
+    write!(f, "Tagged({})", int)
+}
+
+fn main() {
+    let value = Value { float: 1.0 };
+    assert_eq!(format!("{:?}", value), "Value { bits: 0x3f800000, .. }");
+
+    let tagged = Tagged { uint: u32::MAX };
+    assert_eq!(format!("{:?}", tagged), "Tagged(-1)");
+}
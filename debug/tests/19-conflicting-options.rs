@@ -0,0 +1,31 @@
+// Field options which contradict each other are reported instead of one
+// silently winning over the other: a skipped field takes no other option, a
+// field printed by a `with` function can't also be redacted or formatted, and
+// a redacted field has no value to format.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Skipped {
+    #[debug(skip, rename = "hidden")]
+    field: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct Redacted {
+    #[debug(redact)]
+    #[debug = "0x{:02x}"]
+    field: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct Custom {
+    #[debug(with = "fmt_hidden", redact)]
+    field: u8,
+}
+
+fn fmt_hidden(_: &u8, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    f.write_str("hidden")
+}
+
+fn main() {}
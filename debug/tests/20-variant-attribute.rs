@@ -0,0 +1,14 @@
+// Options of CustomDebug go on the type and on fields. A #[debug(...)]
+// attribute on an enum variant would otherwise be silently ignored, so it is
+// reported instead.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub enum Message {
+    Ping,
+    #[debug(skip)]
+    Secret { token: String },
+}
+
+fn main() {}
@@ -8,5 +8,17 @@ fn tests() {
     t.pass("tests/05-phantom-data.rs");
     t.pass("tests/06-bound-trouble.rs");
     t.pass("tests/07-associated-type.rs");
-    //t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/09-enum.rs");
+    t.pass("tests/10-field-bound.rs");
+    t.pass("tests/11-nested-bounds.rs");
+    t.pass("tests/12-trace.rs");
+    t.pass("tests/13-skip-redact.rs");
+    t.pass("tests/14-debug-with.rs");
+    t.pass("tests/15-tuple-struct.rs");
+    t.pass("tests/16-display.rs");
+    t.pass("tests/17-compact-transparent.rs");
+    t.pass("tests/18-union.rs");
+    t.compile_fail("tests/19-conflicting-options.rs");
+    t.compile_fail("tests/20-variant-attribute.rs");
 }